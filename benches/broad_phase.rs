@@ -0,0 +1,121 @@
+//! Compares testing every rocket against every asteroid with only testing the pairs found by the
+//! broad phase grid, at increasing numbers of asteroids.
+//!
+//! The narrow phase is a Rust port of `object_collide`, which measures the distance across the
+//! edges of the world, and is looked up by name for every test, like `invoke_fn!` does. In the
+//! spaceship example each test additionally marshals its arguments into Mun, which makes every
+//! avoided test even more valuable.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mun_examples::{broad_phase::Grid, wrap};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+const WIDTH: f32 = 640.0;
+const HEIGHT: f32 = 360.0;
+const CELL_SIZE: f32 = 32.0;
+const ROCKETS: usize = 5;
+
+struct Circle {
+    center: [f32; 2],
+    radius: f32,
+}
+
+fn random_circles(rng: &mut StdRng, count: usize, radius: f32) -> Vec<Circle> {
+    (0..count)
+        .map(|_| Circle {
+            center: [rng.gen_range(0.0..WIDTH), rng.gen_range(0.0..HEIGHT)],
+            radius,
+        })
+        .collect()
+}
+
+/// Stands in for `object_collide` in `spaceship.mun`.
+fn object_collide(first: &Circle, second: &Circle) -> bool {
+    wrap::distance(first.center, second.center, WIDTH, HEIGHT) - first.radius - second.radius <= 0.0
+}
+
+type CollideFn = fn(&Circle, &Circle) -> bool;
+
+/// Stands in for the runtime's dispatch table.
+struct DispatchTable(HashMap<String, CollideFn>);
+
+impl DispatchTable {
+    fn new() -> Self {
+        let mut functions = HashMap::new();
+        functions.insert("object_collide".to_string(), object_collide as CollideFn);
+        Self(functions)
+    }
+
+    fn invoke(&self, function_name: &str, first: &Circle, second: &Circle) -> bool {
+        (self.0[function_name])(black_box(first), black_box(second))
+    }
+}
+
+fn all_pairs(dispatch: &DispatchTable, rockets: &[Circle], asteroids: &[Circle]) -> usize {
+    let mut collisions = 0;
+    for rocket in rockets {
+        for asteroid in asteroids {
+            if dispatch.invoke("object_collide", rocket, asteroid) {
+                collisions += 1;
+            }
+        }
+    }
+    collisions
+}
+
+fn grid(
+    dispatch: &DispatchTable,
+    grid: &mut Grid,
+    candidates: &mut Vec<usize>,
+    rockets: &[Circle],
+    asteroids: &[Circle],
+) -> usize {
+    grid.clear();
+    for (index, asteroid) in asteroids.iter().enumerate() {
+        grid.insert(index, asteroid.center, asteroid.radius);
+    }
+
+    let mut collisions = 0;
+    for rocket in rockets {
+        grid.query(rocket.center, rocket.radius, candidates);
+        for &index in candidates.iter() {
+            if dispatch.invoke("object_collide", rocket, &asteroids[index]) {
+                collisions += 1;
+            }
+        }
+    }
+    collisions
+}
+
+fn rocket_asteroid_collisions(c: &mut Criterion) {
+    let dispatch = DispatchTable::new();
+    let mut group = c.benchmark_group("rocket_asteroid_collisions");
+    for &count in [100, 250, 500, 1000].iter() {
+        let mut rng = StdRng::seed_from_u64(count as u64);
+        let rockets = random_circles(&mut rng, ROCKETS, 2.0);
+        let asteroids = random_circles(&mut rng, count, 15.0);
+
+        group.bench_with_input(BenchmarkId::new("all_pairs", count), &count, |b, _| {
+            b.iter(|| all_pairs(&dispatch, black_box(&rockets), black_box(&asteroids)))
+        });
+
+        let mut broad_phase = Grid::new(WIDTH, HEIGHT, CELL_SIZE);
+        let mut candidates = Vec::new();
+        group.bench_with_input(BenchmarkId::new("grid", count), &count, |b, _| {
+            b.iter(|| {
+                grid(
+                    &dispatch,
+                    &mut broad_phase,
+                    &mut candidates,
+                    black_box(&rockets),
+                    black_box(&asteroids),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rocket_asteroid_collisions);
+criterion_main!(benches);
@@ -3,51 +3,629 @@ fn main() {
     panic!("Pong needs feature pong enabled: `cargo r --example pong --features pong`")
 }
 
+#[cfg(feature = "pong")]
+fn main() {
+    pong::main()
+}
+
 #[cfg(feature = "pong")]
 mod pong {
     use ggez::{
+        audio::{SoundData, SoundSource, Source},
         event::{self, EventHandler, KeyCode, KeyMods},
-        graphics::{self, DrawMode, DrawParam, FilterMode, Mesh, MeshBuilder, Rect, Text},
-        mint as na,
+        graphics::{
+            self, DrawMode, DrawParam, FilterMode, Font, Image, Mesh, MeshBuilder, PxScale, Rect,
+            Text,
+        },
         mint::Point2,
-        Context, ContextBuilder, GameError, GameResult,
+        timer, Context, ContextBuilder, GameError, GameResult,
+    };
+    use mun_examples::{
+        assets::Manifest,
+        audio::{self, Audio, AudioBackend},
+        background::BackgroundSimulation,
+        debug_draw::{DebugDraw, Layer, Shape},
+        inspect::Value,
+        interpolation::{Interpolator, Pose},
+        invoke,
+        lockstep::Lockstep,
+        logging,
+        net::{self, Connection, Message},
+        overlay::DebugOverlay,
+        profiler, random,
+        render_list::{self, DrawCommand},
+        snapshot::Snapshot,
+        theme::{self, Theme},
+    };
+    use mun_runtime::{RootedStruct, Runtime, RuntimeBuilder, StructRef};
+    use std::{
+        cell::RefCell, collections::HashMap, env, fs, net::TcpListener, path::Path, rc::Rc, thread,
+        time::Duration,
     };
-    use mun_runtime::{invoke_fn, RootedStruct, Runtime, RuntimeBuilder, StructRef};
-    use rand::Rng;
-    use std::{cell::RefCell, rc::Rc};
 
-    extern "C" fn rand_f32() -> f32 {
-        let mut rng = rand::thread_rng();
-        rng.gen()
+    /// The path of the munlib, relative to the working directory.
+    const MUNLIB_PATH: &str = "pong.munlib";
+
+    /// How often the game is updated, ggez's default frame rate.
+    const TICKS_PER_SECOND: u32 = 60;
+    const TICK_DURATION: Duration = Duration::from_nanos(1_000_000_000 / TICKS_PER_SECOND as u64);
+
+    /// The objects that are drawn between the last two ticks.
+    const INTERPOLATED: [&str; 3] = ["ball", "paddle_left", "paddle_right"];
+
+    /// Objects that move further than this in a tick, faster than the ball's top speed, were reset
+    /// rather than moved.
+    const MAX_TICK_DISTANCE: f32 = 100.0;
+
+    /// Loads the munlib with the externs that pong needs.
+    fn spawn_runtime() -> Rc<RefCell<Runtime>> {
+        let runtime = RuntimeBuilder::new(MUNLIB_PATH)
+            .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+        audio::register(render_list::register(logging::register(random::register(
+            runtime,
+        ))))
+        .spawn()
+        .expect("Failed to load munlib")
+    }
+
+    extern "C" fn sqrt(value: f32) -> f32 {
+        value.sqrt()
     }
 
     pub fn marshal_vec2(pos: &StructRef) -> Point2<f32> {
         Point2::from([pos.get("x").unwrap(), pos.get("y").unwrap()])
     }
 
-    fn main() {
-        let (ctx, event_loop) = ContextBuilder::new("Pong", "Mun Team")
+    /// Where the game runs, which decides the paddles that the keyboard controls.
+    enum Network {
+        /// Both players share the keyboard.
+        Local,
+        /// Both players share the keyboard, and the game runs on a thread of its own.
+        Background(Box<BackgroundSimulation>),
+        /// The game runs here, and the right paddle is controlled by a connected instance.
+        Host(Connection),
+        /// The game runs on a host, and the keyboard controls the right paddle.
+        Client(Connection),
+        /// The game runs here and on the other instance, from the same seed. The keyboard sets the
+        /// `input` of `paddle`, which both instances apply when they run the next tick.
+        Lockstep {
+            connection: Connection,
+            lockstep: Box<Lockstep>,
+            paddle: &'static str,
+            input: Snapshot,
+        },
+    }
+
+    impl Network {
+        /// Hosts or joins a game when asked to on the command line, with `--host [address]` or
+        /// `--connect [address]`, or `--lockstep-host [address]` or `--lockstep-connect [address]`
+        /// for a lockstep game. A lockstep game seeds the random numbers of both instances. With
+        /// `--background`, a local game runs on a thread of its own.
+        fn from_args() -> Result<Self, String> {
+            let mut args = env::args().skip(1);
+            let flag = match args.next() {
+                Some(flag) => flag,
+                None => return Ok(Network::Local),
+            };
+            let address = args
+                .next()
+                .unwrap_or_else(|| net::DEFAULT_ADDRESS.to_string());
+
+            match flag.as_str() {
+                "--background" => Ok(Network::Background(Box::new(BackgroundSimulation::spawn(
+                    || Ok(spawn_runtime()),
+                    "new_state",
+                    "update",
+                    TICK_DURATION,
+                )))),
+                "--host" => {
+                    let listener = TcpListener::bind(&address)
+                        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+                    log::info!("Waiting for a player to connect to {}", address);
+                    let connection = Connection::accept(&listener)
+                        .map_err(|e| format!("Failed to accept a player: {}", e))?;
+                    Ok(Network::Host(connection))
+                }
+                "--connect" => Connection::connect(&address)
+                    .map(Network::Client)
+                    .map_err(|e| format!("Failed to connect to {}: {}", address, e)),
+                "--lockstep-host" => {
+                    let listener = TcpListener::bind(&address)
+                        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+                    log::info!("Waiting for a player to connect to {}", address);
+                    let mut connection = Connection::accept(&listener)
+                        .map_err(|e| format!("Failed to accept a player: {}", e))?;
+
+                    let seed = rand::random();
+                    connection
+                        .send(&Message::Start { seed })
+                        .map_err(|e| format!("Failed to start the game: {}", e))?;
+                    random::seed(seed);
+                    Ok(Network::lockstep(
+                        connection,
+                        Lockstep::default(),
+                        "paddle_left",
+                    ))
+                }
+                "--lockstep-connect" => {
+                    let mut connection = Connection::connect(&address)
+                        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+
+                    // The host may already have sent its first tick along with the seed
+                    let mut lockstep = Lockstep::default();
+                    let mut seed = None;
+                    while seed.is_none() {
+                        for message in connection.receive()? {
+                            match message {
+                                Message::Start { seed: start } => seed = Some(start),
+                                message => lockstep.receive(message),
+                            }
+                        }
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    random::seed(seed.unwrap());
+                    Ok(Network::lockstep(connection, lockstep, "paddle_right"))
+                }
+                flag => Err(format!(
+                    "Unknown argument '{}', expected --background, --host, --connect, \
+                     --lockstep-host or --lockstep-connect",
+                    flag
+                )),
+            }
+        }
+
+        fn lockstep(connection: Connection, lockstep: Lockstep, paddle: &'static str) -> Self {
+            Network::Lockstep {
+                connection,
+                lockstep: Box::new(lockstep),
+                paddle,
+                input: Snapshot {
+                    fields: REMOTE_INPUT
+                        .iter()
+                        .map(|field| (field.to_string(), Value::Bool(false)))
+                        .collect(),
+                },
+            }
+        }
+
+        /// Returns the paddle and input field that `keycode` controls.
+        fn paddle_input(&self, keycode: KeyCode) -> Option<(&'static str, &'static str)> {
+            if let Network::Lockstep { paddle, .. } = self {
+                return match keycode {
+                    KeyCode::W | KeyCode::Up => Some((paddle, "move_up")),
+                    KeyCode::S | KeyCode::Down => Some((paddle, "move_down")),
+                    _ => None,
+                };
+            }
+            let shared = matches!(self, Network::Local | Network::Background(_));
+            match (self, keycode) {
+                (Network::Host(_), KeyCode::W) => Some(("paddle_left", "move_up")),
+                (Network::Host(_), KeyCode::S) => Some(("paddle_left", "move_down")),
+                (_, KeyCode::W) if shared => Some(("paddle_left", "move_up")),
+                (_, KeyCode::S) if shared => Some(("paddle_left", "move_down")),
+                (_, KeyCode::Up) if shared => Some(("paddle_right", "move_up")),
+                (_, KeyCode::Down) if shared => Some(("paddle_right", "move_down")),
+                (Network::Client(_), KeyCode::Up | KeyCode::W) => Some(("paddle_right", "move_up")),
+                (Network::Client(_), KeyCode::Down | KeyCode::S) => {
+                    Some(("paddle_right", "move_down"))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// The fields of the right paddle that a connected instance controls.
+    const REMOTE_INPUT: [&str; 2] = ["move_up", "move_down"];
+
+    /// Returns whether `path` is a field of the game state that a connected instance controls.
+    fn is_remote_input(path: &str) -> bool {
+        path.strip_prefix("paddle_right.")
+            .map_or(false, |field| REMOTE_INPUT.contains(&field))
+    }
+
+    /// Returns whether `path` is a field of the game state that the keyboard controls.
+    fn is_input(path: &str) -> bool {
+        ["paddle_left.", "paddle_right."].iter().any(|paddle| {
+            path.strip_prefix(paddle)
+                .map_or(false, |field| REMOTE_INPUT.contains(&field))
+        })
+    }
+
+    pub fn main() {
+        env_logger::init();
+
+        let network = Network::from_args().unwrap_or_else(|e| panic!("{}", e));
+
+        let (mut ctx, event_loop) = ContextBuilder::new("Pong", "Mun Team")
             .build()
             .expect("Failed to initialize ggez");
 
-        let runtime = RuntimeBuilder::new("pong.munlib")
-            .insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
-            .spawn()
-            .expect("Failed to load munlib");
+        // In the background mode, this runtime only draws
+        let runtime = spawn_runtime();
 
         let state = {
             let runtime_ref = runtime.borrow();
-            let state: StructRef = invoke_fn!(&runtime_ref, "new_state").unwrap();
+            let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
             state.root(runtime.clone())
         };
-        let pong = PongGame { runtime, state };
+        let colors = {
+            let runtime_ref = runtime.borrow();
+            let colors: StructRef = invoke!(&runtime_ref, "new_colors").unwrap();
+            colors.root(runtime.clone())
+        };
+        let positions = {
+            let runtime_ref = runtime.borrow();
+            let positions: StructRef = invoke!(&runtime_ref, "new_positions").unwrap();
+            positions.root(runtime.clone())
+        };
+        let assets = Assets::load(&mut ctx).expect("Failed to load assets");
+        let pong = PongGame {
+            runtime,
+            state,
+            colors,
+            positions,
+            assets,
+            meshes: HashMap::new(),
+            network,
+            audio: Audio::default(),
+            music: None,
+            overlay: DebugOverlay::default(),
+            debug_draw: DebugDraw::default(),
+            interpolator: Interpolator::new(MAX_TICK_DISTANCE),
+        };
+        pong.apply_theme();
 
         event::run(ctx, event_loop, pong);
     }
 
+    /// The directory that the asset manifest and theme are loaded from.
+    const ASSETS_PATH: &str = "./assets/pong";
+
+    const CENTER_LINE_WIDTH: f32 = 2.0;
+    const CENTER_LINE_DASH: f32 = 12.0;
+
+    /// The sprites, font, meshes and colors that the game is drawn with, which are created once
+    /// rather than every frame, and its sounds.
+    struct Assets {
+        /// The sprites by id, with their origins.
+        sprites: HashMap<u32, (Image, [f32; 2])>,
+        /// The font of numbers and texts, with its size.
+        font: (Font, f32),
+        /// The texts by id.
+        texts: HashMap<u32, String>,
+        center_line: Mesh,
+        theme: Theme,
+        sounds: HashMap<u32, SoundData>,
+    }
+
+    fn read(path: &Path) -> GameResult<Vec<u8>> {
+        fs::read(path).map_err(|e| {
+            GameError::ResourceLoadError(format!("Failed to read '{}': {}", path.display(), e))
+        })
+    }
+
+    impl Assets {
+        fn load(ctx: &mut Context) -> GameResult<Self> {
+            let manifest = Manifest::load(format!("{}/manifest.txt", ASSETS_PATH))
+                .map_err(GameError::ResourceLoadError)?;
+            let theme = Theme::load(format!("{}/theme.txt", ASSETS_PATH)).unwrap_or_else(|e| {
+                log::warn!("{}, using the default colors", e);
+                Theme::default()
+            });
+
+            let mut sprites = HashMap::new();
+            for sprite in manifest.sprites.iter() {
+                let image = Image::from_bytes(ctx, &read(&sprite.path)?)?;
+                sprites.insert(sprite.id, (image, sprite.origin));
+            }
+            let mut sounds = HashMap::new();
+            for sound in manifest.sounds.iter() {
+                sounds.insert(sound.id, SoundData::from_bytes(&read(&sound.path)?));
+            }
+            let texts = manifest
+                .texts
+                .iter()
+                .map(|text| (text.id, text.text.clone()))
+                .collect();
+            let font = manifest
+                .fonts
+                .iter()
+                .find(|font| font.name == "score")
+                .ok_or_else(|| {
+                    GameError::ResourceLoadError("The manifest lists no 'score' font".to_string())
+                })?;
+
+            Ok(Self {
+                sprites,
+                font: (
+                    Font::new_glyph_font_bytes(ctx, &read(&font.path)?)?,
+                    font.size,
+                ),
+                texts,
+                center_line: center_line_mesh(ctx, theme.color("center_line", GRAY))?,
+                theme,
+                sounds,
+            })
+        }
+
+        fn color(&self, name: &str, default: theme::Color) -> graphics::Color {
+            self.theme.color(name, default).into()
+        }
+    }
+
+    /// Plays the sounds that Mun requests with ggez.
+    struct GgezAudio<'a> {
+        ctx: &'a mut Context,
+        sounds: &'a HashMap<u32, SoundData>,
+        music: &'a mut Option<Source>,
+    }
+
+    impl GgezAudio<'_> {
+        fn source(&mut self, id: u32) -> GameResult<Source> {
+            let data = self.sounds.get(&id).ok_or_else(|| {
+                GameError::AudioError(format!("There is no sound with id {}", id))
+            })?;
+            Source::from_data(self.ctx, data.clone())
+        }
+    }
+
+    impl AudioBackend for GgezAudio<'_> {
+        fn play_sound(&mut self, id: u32) {
+            if let Err(e) = self
+                .source(id)
+                .and_then(|mut sound| sound.play_detached(self.ctx))
+            {
+                log::warn!("Failed to play sound {}: {}", id, e);
+            }
+        }
+
+        fn start_music(&mut self, id: u32) {
+            if let Some(mut music) = self.music.take() {
+                if let Err(e) = music.stop(self.ctx) {
+                    log::warn!("Failed to stop music: {}", e);
+                }
+            }
+            let music = self.source(id).and_then(|mut music| {
+                music.set_repeat(true);
+                music.play(self.ctx)?;
+                Ok(music)
+            });
+            match music {
+                Ok(music) => *self.music = Some(music),
+                Err(e) => log::warn!("Failed to play music {}: {}", id, e),
+            }
+        }
+    }
+
+    const GRAY: theme::Color = [0.5, 0.5, 0.5, 1.0];
+    const BLACK: theme::Color = [0.0, 0.0, 0.0, 1.0];
+
+    /// Builds a dashed line down the middle of the screen.
+    fn center_line_mesh(ctx: &mut Context, color: theme::Color) -> GameResult<Mesh> {
+        let screen = graphics::screen_coordinates(ctx);
+        let x = screen.x + (screen.w - CENTER_LINE_WIDTH) / 2.0;
+
+        let mut builder = MeshBuilder::new();
+        let mut y = screen.y;
+        while y < screen.y + screen.h {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(x, y, CENTER_LINE_WIDTH, CENTER_LINE_DASH),
+                color.into(),
+            )?;
+            y += CENTER_LINE_DASH * 2.0;
+        }
+        builder.build(ctx)
+    }
+
+    const OVERLAY_COLOR: graphics::Color = graphics::Color::new(0.5, 1.0, 0.5, 1.0);
+
+    const PROFILE_REPORT_PATH: &str = "mun_profile.txt";
+
+    /// The number of frames of movement that velocity arrows represent.
+    const VELOCITY_ARROW_FRAMES: f32 = 10.0;
+
     struct PongGame {
         runtime: Rc<RefCell<Runtime>>,
         state: RootedStruct,
+        /// The colors that Mun draws with, by their name in the theme.
+        colors: RootedStruct,
+        /// Where Mun draws the ball and paddles, which may be between two ticks.
+        positions: RootedStruct,
+        assets: Assets,
+        meshes: HashMap<MeshKey, Mesh>,
+        network: Network,
+        audio: Audio,
+        music: Option<Source>,
+        overlay: DebugOverlay,
+        debug_draw: DebugDraw,
+        /// The positions of the ball and paddles after the last two ticks.
+        interpolator: Interpolator<&'static str>,
+    }
+
+    impl PongGame {
+        /// Sets the colors of the `Colors` struct to those of the theme, after the Mun code
+        /// changed.
+        fn apply_theme(&self) {
+            if let Err(e) = self.assets.theme.apply(&mut self.colors.by_ref().clone()) {
+                log::error!("Failed to apply the theme: {}", e);
+            }
+        }
+
+        /// Sets the paddle input that `keycode` controls, and returns whether there is one.
+        fn set_paddle_input(&mut self, keycode: KeyCode, pressed: bool) -> bool {
+            let (paddle, field) = match self.network.paddle_input(keycode) {
+                Some(input) => input,
+                None => return false,
+            };
+
+            if let Network::Lockstep { input, .. } = &mut self.network {
+                // The state only changes when a tick runs, on both instances
+                for (path, value) in input.fields.iter_mut() {
+                    if path == field {
+                        *value = Value::Bool(pressed);
+                    }
+                }
+            } else {
+                let mut paddle = self.state.by_ref().get::<StructRef>(paddle).unwrap();
+                paddle.set(field, pressed).unwrap();
+            }
+            true
+        }
+
+        /// Runs an update of the game, or receives it from the host or the background thread, and
+        /// returns whether the state changed.
+        fn update_state(&mut self, ctx: &mut Context) -> Result<bool, String> {
+            let mut state = self.state.by_ref().clone();
+            let runtime_ref = self.runtime.borrow();
+
+            match &mut self.network {
+                Network::Local => {
+                    let _: () = invoke!(&runtime_ref, "update", state).unwrap();
+                    Ok(true)
+                }
+                Network::Background(simulation) => {
+                    let mut input = Snapshot::capture(&state);
+                    input.fields.retain(|(path, _)| is_input(path));
+                    simulation.set_input(input);
+
+                    let frames = simulation.receive()?;
+                    if frames.is_empty() {
+                        return Ok(false);
+                    }
+                    for frame in frames {
+                        self.audio.play(
+                            frame.audio_requests,
+                            &mut GgezAudio {
+                                ctx,
+                                sounds: &self.assets.sounds,
+                                music: &mut self.music,
+                            },
+                        );
+                    }
+
+                    // Keep the keyboard's input, which the simulation hasn't seen yet
+                    let mut latest = simulation.latest().unwrap().clone();
+                    latest.fields.retain(|(path, _)| !is_input(path));
+                    match latest.restore(&mut state) {
+                        Ok(()) => Ok(true),
+                        Err(e) => {
+                            // This runtime hasn't reloaded the munlib that the simulation runs yet
+                            log::debug!("Skipped a state: {}", e);
+                            Ok(false)
+                        }
+                    }
+                }
+                Network::Host(connection) => {
+                    // Only accept the input of the connected player
+                    let mut paddle = state.get::<StructRef>("paddle_right").unwrap();
+                    for message in connection.receive()? {
+                        if let Message::Input(mut input) = message {
+                            input
+                                .fields
+                                .retain(|(field, _)| REMOTE_INPUT.contains(&field.as_str()));
+                            input.restore(&mut paddle)?;
+                        }
+                    }
+
+                    let _: () = invoke!(&runtime_ref, "update", state.clone()).unwrap();
+                    connection
+                        .send(&Message::State(Snapshot::capture(&state)))
+                        .map_err(|e| format!("Failed to send the game state: {}", e))?;
+                    Ok(true)
+                }
+                Network::Client(connection) => {
+                    let mut input =
+                        Snapshot::capture(&state.get::<StructRef>("paddle_right").unwrap());
+                    input
+                        .fields
+                        .retain(|(field, _)| REMOTE_INPUT.contains(&field.as_str()));
+                    connection
+                        .send(&Message::Input(input))
+                        .map_err(|e| format!("Failed to send input: {}", e))?;
+
+                    // Only the latest state matters, except for the input of this player, which
+                    // the host hasn't seen yet
+                    let latest =
+                        connection
+                            .receive()?
+                            .into_iter()
+                            .rev()
+                            .find_map(|message| match message {
+                                Message::State(state) => Some(state),
+                                _ => None,
+                            });
+                    match latest {
+                        Some(mut latest) => {
+                            latest.fields.retain(|(path, _)| !is_remote_input(path));
+                            latest.restore(&mut state)?;
+                            Ok(true)
+                        }
+                        None => Ok(false),
+                    }
+                }
+                Network::Lockstep {
+                    connection,
+                    lockstep,
+                    paddle,
+                    input,
+                } => {
+                    lockstep.set_input(|| input.clone(), || Snapshot::capture(&state));
+                    for message in connection.receive()? {
+                        lockstep.receive(message);
+                    }
+                    for message in lockstep.outgoing() {
+                        connection
+                            .send(&message)
+                            .map_err(|e| format!("Failed to send input: {}", e))?;
+                    }
+                    if let Some(desync) = lockstep.desync() {
+                        return Err(desync.to_string());
+                    }
+
+                    // Wait for the other instance's input, before running the tick
+                    if let Some((local, mut remote)) = lockstep.advance() {
+                        let other = if *paddle == "paddle_left" {
+                            "paddle_right"
+                        } else {
+                            "paddle_left"
+                        };
+                        local.restore(&mut state.get::<StructRef>(paddle).unwrap())?;
+                        remote
+                            .fields
+                            .retain(|(field, _)| REMOTE_INPUT.contains(&field.as_str()));
+                        remote.restore(&mut state.get::<StructRef>(other).unwrap())?;
+
+                        let _: () = invoke!(&runtime_ref, "update", state).unwrap();
+                        Ok(true)
+                    } else {
+                        Ok(false)
+                    }
+                }
+            }
+        }
+
+        /// Records the positions of the ball and paddles after a tick, to draw them between the
+        /// last two ticks.
+        fn record_poses(&mut self) {
+            self.interpolator.begin_tick();
+            let state = self.state.by_ref();
+            for &name in INTERPOLATED.iter() {
+                let object = state.get::<StructRef>(name).unwrap();
+                self.interpolator.record(name, pose(&object));
+            }
+        }
+
+        /// Returns how far the drawn state is from the previous tick, `0.0`, to the current one,
+        /// `1.0`.
+        fn alpha(&self, ctx: &mut Context) -> f32 {
+            match &self.network {
+                Network::Background(simulation) => simulation.alpha(),
+                _ => (timer::remaining_update_time(ctx).as_secs_f32()
+                    / TICK_DURATION.as_secs_f32())
+                .min(1.0),
+            }
+        }
     }
 
     impl EventHandler<GameError> for PongGame {
@@ -55,112 +633,132 @@ mod pong {
             &mut self,
             ctx: &mut Context,
             keycode: KeyCode,
-            _keymods: KeyMods,
-            _repeat: bool,
+            keymods: KeyMods,
+            repeat: bool,
         ) {
+            if self.set_paddle_input(keycode, true) {
+                return;
+            }
             let state = self.state.by_ref();
             match keycode {
-                KeyCode::W => {
-                    let mut paddle = state.get::<StructRef>("paddle_left").unwrap();
-                    paddle.set("move_up", true).unwrap();
+                KeyCode::Escape => {
+                    event::quit(ctx);
                 }
-                KeyCode::S => {
-                    let mut paddle = state.get::<StructRef>("paddle_left").unwrap();
-                    paddle.set("move_down", true).unwrap();
+                KeyCode::F1 if !repeat => {
+                    self.overlay.toggle();
                 }
-                KeyCode::Up => {
-                    let mut paddle = state.get::<StructRef>("paddle_right").unwrap();
-                    paddle.set("move_up", true).unwrap();
+                KeyCode::F2 if !repeat => {
+                    self.debug_draw.toggle();
                 }
-                KeyCode::Down => {
-                    let mut paddle = state.get::<StructRef>("paddle_right").unwrap();
-                    paddle.set("move_down", true).unwrap();
+                KeyCode::F3 if !repeat => {
+                    self.overlay.toggle_profile();
                 }
-                KeyCode::Escape => {
-                    event::quit(ctx);
+                KeyCode::F4 if !repeat => dump_profile(),
+                KeyCode::LBracket if self.overlay.is_state_visible() => {
+                    self.overlay.select_previous(&state);
+                }
+                KeyCode::RBracket if self.overlay.is_state_visible() => {
+                    self.overlay.select_next(&state);
+                }
+                KeyCode::Minus | KeyCode::Equals if self.overlay.is_state_visible() => {
+                    let steps = if keycode == KeyCode::Minus { -1 } else { 1 };
+                    let fine = keymods.contains(KeyMods::SHIFT);
+                    let mut state = state.clone();
+                    if let Err(e) = self.overlay.adjust_selected(&mut state, steps, fine) {
+                        log::warn!("Failed to tweak `PongState`: {}", e);
+                    }
                 }
                 _ => (),
             }
         }
 
         fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods) {
-            let state = self.state.by_ref();
-            match keycode {
-                KeyCode::W => {
-                    let mut paddle = state.get::<StructRef>("paddle_left").unwrap();
-                    paddle.set("move_up", false).unwrap();
-                }
-                KeyCode::S => {
-                    let mut paddle = state.get::<StructRef>("paddle_left").unwrap();
-                    paddle.set("move_down", false).unwrap();
-                }
-                KeyCode::Up => {
-                    let mut paddle = state.get::<StructRef>("paddle_right").unwrap();
-                    paddle.set("move_up", false).unwrap();
+            self.set_paddle_input(keycode, false);
+        }
+
+        fn update(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
+            profiler::end_frame();
+            self.overlay
+                .set_counter("FPS", format!("{:.0}", timer::fps(ctx)));
+
+            // The game ticks at a fixed rate, or whenever the background thread ticked
+            let ticks = if let Network::Background(_) = self.network {
+                1
+            } else {
+                let mut ticks = 0;
+                while timer::check_update_time(ctx, TICKS_PER_SECOND) {
+                    ticks += 1;
                 }
-                KeyCode::Down => {
-                    let mut paddle = state.get::<StructRef>("paddle_right").unwrap();
-                    paddle.set("move_down", false).unwrap();
+                ticks
+            };
+            for _ in 0..ticks {
+                match self.update_state(ctx) {
+                    Ok(true) => self.record_poses(),
+                    Ok(false) => (),
+                    Err(e) => {
+                        log::error!("{}", e);
+                        event::quit(ctx);
+                        break;
+                    }
                 }
-                _ => (),
             }
-        }
+            self.audio.play_requests(&mut GgezAudio {
+                ctx,
+                sounds: &self.assets.sounds,
+                music: &mut self.music,
+            });
 
-        fn update(&mut self, _ctx: &mut ggez::Context) -> ggez::GameResult {
-            {
-                let state = self.state.by_ref().clone();
-                let runtime_ref = self.runtime.borrow();
-                let _: () = invoke_fn!(&runtime_ref, "update", state).unwrap();
+            if self.runtime.borrow_mut().update() {
+                // Colors that were added to `Colors` are only set by the theme
+                self.apply_theme();
             }
-
-            self.runtime.borrow_mut().update();
             Ok(())
         }
 
         fn draw(&mut self, ctx: &mut ggez::Context) -> ggez::GameResult {
-            graphics::clear(ctx, graphics::Color::BLACK);
+            graphics::clear(ctx, self.assets.color("background", BLACK));
+            graphics::draw(ctx, &self.assets.center_line, DrawParam::default())?;
 
+            let alpha = self.alpha(ctx);
             let state = self.state.by_ref();
             let runtime_ref = self.runtime.borrow();
 
-            let ball = state.get::<StructRef>("ball").unwrap();
-            let paddle_left = state.get::<StructRef>("paddle_left").unwrap();
-            let paddle_right = state.get::<StructRef>("paddle_right").unwrap();
-
-            let ball_mesh = MeshBuilder::new()
-                .circle(
-                    DrawMode::fill(),
-                    [0., 0.],
-                    invoke_fn!(&runtime_ref, "ball_radius").unwrap(),
-                    invoke_fn!(&runtime_ref, "ball_tolerance").unwrap(),
-                    graphics::Color::WHITE,
-                )?
-                .build(ctx)?;
-            draw_mesh(ctx, &ball_mesh, &ball)?;
-
-            let paddle_mesh = MeshBuilder::new()
-                .rectangle(
-                    DrawMode::fill(),
-                    bounds(
-                        invoke_fn!(&runtime_ref, "paddle_width").unwrap(),
-                        invoke_fn!(&runtime_ref, "paddle_height").unwrap(),
-                    ),
-                    graphics::Color::WHITE,
-                )?
-                .build(ctx)?;
-            draw_mesh(ctx, &paddle_mesh, &paddle_left)?;
-            draw_mesh(ctx, &paddle_mesh, &paddle_right)?;
-
-            queue_score_text(
-                ctx,
-                &paddle_left,
-                marshal_vec2(&invoke_fn!(&runtime_ref, "left_score_pos").unwrap()),
-            );
-            queue_score_text(
-                ctx,
-                &paddle_right,
-                marshal_vec2(&invoke_fn!(&runtime_ref, "right_score_pos").unwrap()),
-            );
+            // Draw the ball and paddles between the last two ticks
+            let mut positions = self.positions.by_ref().clone();
+            for name in INTERPOLATED.iter() {
+                let pose = self
+                    .interpolator
+                    .pose(name, alpha)
+                    .unwrap_or_else(|| pose(&state.get::<StructRef>(name).unwrap()));
+                set_position(&mut positions, name, pose.position);
+            }
+
+            // Let Mun fill the render list, and draw it
+            let _: () = invoke!(
+                &runtime_ref,
+                "draw",
+                state.clone(),
+                self.colors.by_ref().clone(),
+                positions.clone()
+            )
+            .unwrap();
+            draw_commands(ctx, &self.assets, &mut self.meshes, &render_list::take())?;
+
+            if self.debug_draw.is_enabled() {
+                self.debug_draw.clear();
+                collect_debug_shapes(
+                    &mut self.debug_draw,
+                    &runtime_ref,
+                    &state.get::<StructRef>("ball").unwrap(),
+                    &positions,
+                );
+                draw_debug_shapes(ctx, &self.debug_draw)?;
+            }
+
+            if self.overlay.is_visible() {
+                let overlay_text = Text::new(self.overlay.lines(&state).join("\n"));
+                graphics::queue_text(ctx, &overlay_text, [10.0, 40.0], Some(OVERLAY_COLOR));
+            }
             graphics::draw_queued_text(ctx, DrawParam::default(), None, FilterMode::Linear)?;
 
             graphics::present(ctx)?;
@@ -168,25 +766,231 @@ mod pong {
         }
     }
 
-    fn bounds(width: f32, height: f32) -> Rect {
-        Rect::new(0.0, 0.0, width, height)
+    fn pose(object: &StructRef) -> Pose {
+        Pose {
+            position: marshal_vec2(&object.get("pos").unwrap()).into(),
+            angle: 0.0,
+        }
     }
 
-    fn draw_mesh(ctx: &mut Context, mesh: &Mesh, object: &StructRef) -> GameResult {
+    /// Sets the field called `name` of a Mun `Positions` struct, where Mun draws an object.
+    fn set_position(positions: &mut StructRef, name: &str, position: [f32; 2]) {
+        let mut pos = positions.get::<StructRef>(name).unwrap();
+        pos.set("x", position[0]).unwrap();
+        pos.set("y", position[1]).unwrap();
+        positions.set(name, pos).unwrap();
+    }
+
+    /// Shapes whose size or color changes every frame would each add a mesh, so the cache is
+    /// cleared when it grows beyond this.
+    const MAX_MESHES: usize = 256;
+
+    /// A shape of a render list and its color, from which a mesh is built once.
+    #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+    enum MeshKey {
+        Rect { size: [u32; 2], color: [u32; 4] },
+        Circle { radius: u32, color: [u32; 4] },
+    }
+
+    fn color_bits(color: theme::Color) -> [u32; 4] {
+        color.map(f32::to_bits)
+    }
+
+    /// Builds the mesh of `key`, with the top-left corner of a rectangle or the center of a
+    /// circle at the origin.
+    fn build_mesh(ctx: &mut Context, key: MeshKey) -> GameResult<Mesh> {
+        let mut builder = MeshBuilder::new();
+        match key {
+            MeshKey::Rect { size, color } => builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(0.0, 0.0, f32::from_bits(size[0]), f32::from_bits(size[1])),
+                color.map(f32::from_bits).into(),
+            )?,
+            MeshKey::Circle { radius, color } => builder.circle(
+                DrawMode::fill(),
+                [0.0, 0.0],
+                f32::from_bits(radius),
+                0.1,
+                color.map(f32::from_bits).into(),
+            )?,
+        };
+        builder.build(ctx)
+    }
+
+    /// Draws the mesh of `key` at `position`, building it if it isn't in `meshes` yet.
+    fn draw_mesh(
+        ctx: &mut Context,
+        meshes: &mut HashMap<MeshKey, Mesh>,
+        key: MeshKey,
+        position: [f32; 2],
+    ) -> GameResult {
+        if !meshes.contains_key(&key) {
+            if meshes.len() >= MAX_MESHES {
+                meshes.clear();
+            }
+            meshes.insert(key, build_mesh(ctx, key)?);
+        }
+        graphics::draw(ctx, &meshes[&key], DrawParam::new().dest(position))
+    }
+
+    /// Draws the commands of a render list in order. Every shape is drawn with a mesh that is
+    /// only built the first time that the shape is drawn in its color.
+    fn draw_commands(
+        ctx: &mut Context,
+        assets: &Assets,
+        meshes: &mut HashMap<MeshKey, Mesh>,
+        commands: &[DrawCommand],
+    ) -> GameResult {
+        for command in commands {
+            match *command {
+                DrawCommand::Rect {
+                    position,
+                    width,
+                    height,
+                    color,
+                } => {
+                    let key = MeshKey::Rect {
+                        size: [width.to_bits(), height.to_bits()],
+                        color: color_bits(color),
+                    };
+                    draw_mesh(ctx, meshes, key, position)?;
+                }
+                DrawCommand::Circle {
+                    center,
+                    radius,
+                    color,
+                } => {
+                    let key = MeshKey::Circle {
+                        radius: radius.to_bits(),
+                        color: color_bits(color),
+                    };
+                    draw_mesh(ctx, meshes, key, center)?;
+                }
+                DrawCommand::Sprite {
+                    id,
+                    position,
+                    rotation,
+                    scale,
+                    color,
+                } => {
+                    if let Some((image, origin)) = assets.sprites.get(&id) {
+                        graphics::draw(
+                            ctx,
+                            image,
+                            DrawParam::new()
+                                .dest(position)
+                                .offset([
+                                    origin[0] / f32::from(image.width()),
+                                    origin[1] / f32::from(image.height()),
+                                ])
+                                .rotation(rotation.to_radians())
+                                .scale(scale)
+                                .color(color.into()),
+                        )?;
+                    }
+                }
+                DrawCommand::Number {
+                    value,
+                    position,
+                    color,
+                } => draw_text(ctx, assets, &value.to_string(), position, color)?,
+                DrawCommand::Text {
+                    id,
+                    position,
+                    color,
+                } => {
+                    if let Some(text) = assets.texts.get(&id) {
+                        draw_text(ctx, assets, text, position, color)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_text(
+        ctx: &mut Context,
+        assets: &Assets,
+        text: &str,
+        position: [f32; 2],
+        color: theme::Color,
+    ) -> GameResult {
+        let (font, size) = assets.font;
+        let mut text = Text::new(text);
+        text.set_font(font, PxScale::from(size));
         graphics::draw(
             ctx,
-            mesh,
-            (
-                marshal_vec2(&object.get("pos").unwrap()),
-                0.0,
-                graphics::Color::WHITE,
-            ),
+            &text,
+            DrawParam::new().dest(position).color(color.into()),
         )
     }
 
-    fn queue_score_text(ctx: &mut Context, paddle: &StructRef, score_pos: na::Point2<f32>) {
-        let score = paddle.get::<u32>("score").unwrap();
-        let score_text = Text::new(score.to_string());
-        graphics::queue_text(ctx, &score_text, score_pos, Some(graphics::Color::WHITE));
+    fn dump_profile() {
+        match profiler::write_report(PROFILE_REPORT_PATH) {
+            Ok(()) => log::info!("Wrote Mun profile to {}", PROFILE_REPORT_PATH),
+            Err(e) => log::error!("Failed to write Mun profile: {}", e),
+        }
+    }
+
+    /// Collects the shapes of the ball and paddles, at the `positions` they are drawn at.
+    fn collect_debug_shapes(
+        debug_draw: &mut DebugDraw,
+        runtime: &Runtime,
+        ball: &StructRef,
+        positions: &StructRef,
+    ) {
+        let paddle_width: f32 = invoke!(runtime, "paddle_width").unwrap();
+        let paddle_height: f32 = invoke!(runtime, "paddle_height").unwrap();
+        for paddle in ["paddle_left", "paddle_right"] {
+            let pos: [f32; 2] = marshal_vec2(&positions.get(paddle).unwrap()).into();
+            debug_draw.rect(Layer::Collider, pos, paddle_width, paddle_height);
+            debug_draw.cross(Layer::Origin, pos, 6.0);
+        }
+
+        let ball_radius: f32 = invoke!(runtime, "ball_radius").unwrap();
+        let pos: [f32; 2] = marshal_vec2(&positions.get("ball").unwrap()).into();
+        let vel = marshal_vec2(&ball.get("vel").unwrap());
+        debug_draw.circle(Layer::Collider, pos, ball_radius);
+        debug_draw.arrow(
+            Layer::Velocity,
+            pos,
+            [
+                pos[0] + vel.x * VELOCITY_ARROW_FRAMES,
+                pos[1] + vel.y * VELOCITY_ARROW_FRAMES,
+            ],
+        );
+        debug_draw.cross(Layer::Origin, pos, 6.0);
+    }
+
+    fn draw_debug_shapes(ctx: &mut Context, debug_draw: &DebugDraw) -> GameResult {
+        if debug_draw.shapes().is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = MeshBuilder::new();
+        for (layer, shape) in debug_draw.shapes() {
+            let color = match layer {
+                Layer::Collider => graphics::Color::new(1.0, 0.2, 0.2, 1.0),
+                Layer::Velocity => graphics::Color::new(0.2, 0.6, 1.0, 1.0),
+                Layer::Origin => graphics::Color::new(1.0, 1.0, 0.2, 1.0),
+            };
+            match *shape {
+                Shape::Circle { center, radius } => {
+                    builder.circle(DrawMode::stroke(1.0), center, radius, 0.1, color)?
+                }
+                Shape::Rect {
+                    position,
+                    width,
+                    height,
+                } => builder.rectangle(
+                    DrawMode::stroke(1.0),
+                    Rect::new(position[0], position[1], width, height),
+                    color,
+                )?,
+                Shape::Line { from, to } => builder.line(&[from, to], 1.0, color)?,
+            };
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
     }
 }
@@ -0,0 +1,107 @@
+//! Plays many games of pong at once without a window, with a simple AI for both paddles, and
+//! prints the scores. Run with `cargo run --example pong_batch -- [games] [ticks]`.
+
+use mun_examples::{
+    audio, inspect::Value, instances::Instances, invoke, logging, random, render_list,
+    snapshot::Snapshot,
+};
+use mun_runtime::{RuntimeBuilder, StructRef};
+use std::{env, time::Instant};
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+/// Moves the paddle towards the ball, but only once the ball is further than `reaction` from the
+/// center of the paddle.
+fn follow_ball(
+    state: &StructRef,
+    paddle: &str,
+    paddle_height: f32,
+    reaction: f32,
+) -> Vec<(String, Value)> {
+    let ball_y: f32 = state
+        .get::<StructRef>("ball")
+        .and_then(|ball| ball.get::<StructRef>("pos"))
+        .and_then(|pos| pos.get("y"))
+        .unwrap();
+    let paddle_y: f32 = state
+        .get::<StructRef>(paddle)
+        .and_then(|paddle| paddle.get::<StructRef>("pos"))
+        .and_then(|pos| pos.get("y"))
+        .unwrap();
+    let offset = ball_y - (paddle_y + paddle_height / 2.0);
+    vec![
+        (
+            format!("{}.move_up", paddle),
+            Value::Bool(offset < -reaction),
+        ),
+        (
+            format!("{}.move_down", paddle),
+            Value::Bool(offset > reaction),
+        ),
+    ]
+}
+
+fn main() {
+    env_logger::init();
+
+    let mut args = env::args().skip(1);
+    let games: usize = args.next().map_or(16, |games| games.parse().unwrap());
+    let ticks: u64 = args.next().map_or(3600, |ticks| ticks.parse().unwrap());
+
+    let runtime = RuntimeBuilder::new("pong.munlib")
+        .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+    let runtime = audio::register(render_list::register(logging::register(random::register(
+        runtime,
+    ))))
+    .spawn()
+    .expect("Failed to load munlib");
+    let paddle_height: f32 = invoke!(&runtime.borrow(), "paddle_height").unwrap();
+    let mut instances =
+        Instances::new(runtime, games, 0, "new_state").expect("Failed to create the games");
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        // The left paddle reacts alike in every game, the right one later in every next game
+        for index in 0..instances.len() {
+            let instance = instances.get_mut(index).unwrap();
+            let state = instance.state();
+            let mut fields = follow_ball(&state, "paddle_left", paddle_height, 20.0);
+            fields.extend(follow_ball(
+                &state,
+                "paddle_right",
+                paddle_height,
+                5.0 * index as f32,
+            ));
+            instance.input = Snapshot { fields };
+        }
+        instances
+            .step(|_, runtime, state| {
+                invoke!(runtime, "update", state).map_err(|e| format!("{:?}", e))
+            })
+            .expect("Failed to update the games");
+    }
+
+    println!(
+        "Played {} games of {} ticks in {:.2?}",
+        games,
+        ticks,
+        start.elapsed()
+    );
+    for (index, instance) in instances.iter().enumerate() {
+        let state = instance.state();
+        let score = |paddle: &str| -> u32 {
+            state
+                .get::<StructRef>(paddle)
+                .and_then(|paddle| paddle.get("score"))
+                .unwrap()
+        };
+        println!(
+            "Game {:>3}: {:>3} - {:<3}",
+            index,
+            score("paddle_left"),
+            score("paddle_right")
+        );
+    }
+}
@@ -3,11 +3,18 @@ fn main() {
     panic!("Spaceship needs feature spaceship enabled: `cargo r --example spaceship --features spaceship`")
 }
 
+#[cfg(feature = "spaceship")]
+fn main() -> tetra::Result {
+    spaceship::main()
+}
+
 #[cfg(feature = "spaceship")]
 mod spaceship {
-    use tetra::graphics::{self, Color, DrawParams, Texture};
+    use tetra::audio::{Sound, SoundInstance};
+    use tetra::graphics::mesh::{GeometryBuilder, ShapeStyle};
+    use tetra::graphics::{self, Color, DrawParams, Rectangle, Texture};
     use tetra::math::Vec2;
-    use tetra::{Context, ContextBuilder, State};
+    use tetra::{time, Context, ContextBuilder, State, TetraError};
 
     use tetra::graphics::scaling::{ScalingMode, ScreenScaler};
 
@@ -15,76 +22,452 @@ mod spaceship {
 
     use tetra::input::{self, Key};
 
-    use mun_runtime::{invoke_fn, RootedStruct, RuntimeBuilder, StructRef};
+    use mun_examples::{
+        assets::{FileWatcher, Manifest},
+        audio::{self, Audio, AudioBackend},
+        debug_draw::{self, DebugDraw, Layer, Shape},
+        function_cache::{FunctionCache, FunctionId},
+        inspect::{self, Value},
+        interpolation::{self, Interpolator, Pose},
+        invoke,
+        lockstep::Lockstep,
+        logging,
+        net::{self, Connection, Message},
+        overlay::DebugOverlay,
+        particles::{self, Effect, Emitter, ParticleSystem},
+        profiler, random,
+        render_list::{self, DrawCommand},
+        snapshot::Snapshot,
+        spaceship::{
+            self as game, game_area_height, game_area_width, SavedSpaceship, Spaceship,
+            PLAYER_INPUTS,
+        },
+        theme::{self, Theme},
+        wrap,
+    };
+    use mun_runtime::{RootedStruct, RuntimeBuilder, StructRef};
+
+    use std::collections::{HashMap, VecDeque};
+    use std::env;
+    use std::iter;
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+
+    const ASSET_MANIFEST_PATH: &str = "./assets/spaceship/manifest.txt";
+    const THEME_PATH: &str = "./assets/spaceship/theme.txt";
+
+    /// The sprites and fonts listed by the asset manifest, by name, and the colors of the theme.
+    /// Sprites can also be found by their id.
+    struct Assets {
+        manifest: Manifest,
+        theme: Theme,
+        sprites: HashMap<String, (Texture, Vec2<f32>)>,
+        sprite_names: HashMap<u32, String>,
+        fonts: HashMap<String, Font>,
+        sounds: HashMap<u32, Sound>,
+        watcher: FileWatcher,
+    }
 
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    impl Assets {
+        fn load(ctx: &mut Context) -> Result<Self, String> {
+            let manifest = Manifest::load(ASSET_MANIFEST_PATH)?;
+            let theme = Theme::load(THEME_PATH)?;
+            let failed =
+                |path: &Path, e: TetraError| format!("Failed to load '{}': {}", path.display(), e);
+
+            let mut sprites = HashMap::new();
+            let mut sprite_names = HashMap::new();
+            for sprite in manifest.sprites.iter() {
+                let texture =
+                    Texture::new(ctx, &sprite.path).map_err(|e| failed(&sprite.path, e))?;
+                sprites.insert(sprite.name.clone(), (texture, Vec2::from(sprite.origin)));
+                sprite_names.insert(sprite.id, sprite.name.clone());
+            }
+
+            let mut fonts = HashMap::new();
+            for font in manifest.fonts.iter() {
+                let loaded =
+                    Font::vector(ctx, &font.path, font.size).map_err(|e| failed(&font.path, e))?;
+                fonts.insert(font.name.clone(), loaded);
+            }
 
-    use rand::prelude::*;
+            let mut sounds = HashMap::new();
+            for sound in manifest.sounds.iter() {
+                let loaded = Sound::new(&sound.path).map_err(|e| failed(&sound.path, e))?;
+                sounds.insert(sound.id, loaded);
+            }
+
+            Ok(Self {
+                watcher: FileWatcher::new(
+                    [Path::new(ASSET_MANIFEST_PATH), Path::new(THEME_PATH)]
+                        .iter()
+                        .copied()
+                        .chain(manifest.files()),
+                ),
+                manifest,
+                theme,
+                sprites,
+                sprite_names,
+                fonts,
+                sounds,
+            })
+        }
+
+        /// Reloads all assets if the manifest or any of the files it lists changed, and returns
+        /// whether they were. The current assets are kept if that fails.
+        fn reload_if_changed(&mut self, ctx: &mut Context) -> bool {
+            if !self.watcher.poll() {
+                return false;
+            }
 
-    extern "C" fn sin(number: f32) -> f32 {
-        number.sin()
+            match Assets::load(ctx) {
+                Ok(assets) => {
+                    log::info!("Reloaded assets");
+                    *self = assets;
+                    true
+                }
+                Err(e) => {
+                    log::error!("Failed to reload assets: {}", e);
+                    false
+                }
+            }
+        }
+
+        fn sprite(&self, id: u32) -> Option<&(Texture, Vec2<f32>)> {
+            self.sprite_names
+                .get(&id)
+                .and_then(|name| self.sprites.get(name))
+        }
+
+        fn font(&self, name: &str) -> Option<&Font> {
+            self.fonts.get(name)
+        }
+
+        /// Sets every field of `sprites`, a Mun `Sprites` struct, to the id of the sprite that the
+        /// field is named after.
+        fn resolve_sprites(&self, sprites: &mut StructRef) -> Result<(), String> {
+            for field in inspect::fields(sprites) {
+                let sprite = self
+                    .manifest
+                    .sprite(&field.path)
+                    .ok_or_else(|| format!("There is no sprite called `{}`", field.path))?;
+                inspect::set(sprites, &field.path, Value::U32(sprite.id))?;
+            }
+            Ok(())
+        }
     }
 
-    extern "C" fn cos(number: f32) -> f32 {
-        number.cos()
+    /// Plays the sounds that Mun requests with tetra.
+    struct TetraAudio<'a> {
+        ctx: &'a Context,
+        sounds: &'a HashMap<u32, Sound>,
+        music: &'a mut Option<SoundInstance>,
     }
 
-    extern "C" fn dbg(number: f32) {
-        dbg!(number);
+    impl AudioBackend for TetraAudio<'_> {
+        fn play_sound(&mut self, id: u32) {
+            match self.sounds.get(&id).map(|sound| sound.play(self.ctx)) {
+                Some(Ok(_)) => (),
+                Some(Err(e)) => log::warn!("Failed to play sound {}: {}", id, e),
+                None => log::warn!("There is no sound with id {}", id),
+            }
+        }
+
+        fn start_music(&mut self, id: u32) {
+            if let Some(music) = self.music.take() {
+                music.stop();
+            }
+            match self.sounds.get(&id).map(|sound| sound.repeat(self.ctx)) {
+                Some(Ok(music)) => *self.music = Some(music),
+                Some(Err(e)) => log::warn!("Failed to play music {}: {}", id, e),
+                None => log::warn!("There is no sound with id {}", id),
+            }
+        }
     }
 
-    extern "C" fn degrees_to_radians(degrees: f32) -> f32 {
-        degrees.to_radians()
+    /// The keys that control the fields of `PlayerInput`.
+    const KEYS: [(&str, Key); 4] = [
+        ("left", Key::Left),
+        ("right", Key::Right),
+        ("up", Key::Up),
+        ("shoot", Key::Z),
+    ];
+
+    const OVERLAY_COLOR: Color = Color::rgb(0.5, 1.0, 0.5);
+
+    const PROFILE_REPORT_PATH: &str = "mun_profile.txt";
+
+    /// The number of frames of movement that velocity arrows represent.
+    const VELOCITY_ARROW_FRAMES: f32 = 10.0;
+
+    /// The number of frames that can be rewound, five seconds at 60 FPS.
+    const REWIND_FRAMES: usize = 300;
+
+    /// Objects that move further than this in a tick, several times the fastest speed, respawned
+    /// rather than moved.
+    const MAX_TICK_DISTANCE: f32 = 20.0;
+
+    /// The exhaust of the spaceship's engine, emitted every frame while thrusting.
+    const THRUST_EMITTER: Emitter = Emitter {
+        count: 2,
+        direction: 0.0,
+        spread: 15.0,
+        speed: [1.0, 2.0],
+        lifetime: [8.0, 15.0],
+        size: 1.0,
+        start_color: [1.0, 1.0, 0.6, 1.0],
+        end_color: [1.0, 0.3, 0.0, 0.0],
+    };
+
+    /// The debris of a destroyed object with a radius of `size`.
+    fn explosion_emitter(size: f32) -> Emitter {
+        Emitter {
+            count: (size * 2.0) as usize,
+            direction: 0.0,
+            spread: 180.0,
+            speed: [0.2, 0.5 + size * 0.05],
+            lifetime: [20.0, 40.0],
+            size: 2.0,
+            start_color: [1.0, 0.8, 0.3, 1.0],
+            end_color: [0.6, 0.1, 0.0, 0.0],
+        }
     }
 
-    extern "C" fn sqrt(value: f32) -> f32 {
-        value.sqrt()
+    /// The Mun functions that are invoked to draw every rocket and asteroid, each frame.
+    struct EntityFunctions {
+        cache: FunctionCache,
+        draw_rocket: FunctionId,
+        draw_asteroid: FunctionId,
     }
 
-    extern "C" fn game_area_width() -> f32 {
-        128.0 * 5.0
+    impl EntityFunctions {
+        fn new() -> Self {
+            let mut cache = FunctionCache::default();
+            Self {
+                draw_rocket: cache.register("draw_rocket"),
+                draw_asteroid: cache.register("draw_asteroid"),
+                cache,
+            }
+        }
     }
 
-    extern "C" fn game_area_height() -> f32 {
-        72.0 * 5.0
+    /// A game that runs here and on another instance, from the same seed. The keyboard controls
+    /// the fields of `PlayerInput` that belong to `player`, and both instances apply the input of
+    /// both players when they run the next tick.
+    struct Peer {
+        connection: Connection,
+        lockstep: Lockstep,
+        player: usize,
     }
 
-    fn textures(ctx: &mut Context) -> [(Texture, Vec2<f32>); 5] {
-        [
-            (
-                Texture::new(ctx, "./assets/spaceship/sprites/spaceship.png").unwrap(),
-                Vec2::new(6., 7.),
-            ),
-            (
-                Texture::new(ctx, "./assets/spaceship/sprites/rocket.png").unwrap(),
-                Vec2::new(3., 3.),
-            ),
-            (
-                Texture::new(ctx, "./assets/spaceship/sprites/asteroid_size_1.png").unwrap(),
-                Vec2::new(5.0, 5.0),
-            ),
-            (
-                Texture::new(ctx, "./assets/spaceship/sprites/asteroid_size_2.png").unwrap(),
-                Vec2::new(8.0, 8.0),
-            ),
-            (
-                Texture::new(ctx, "./assets/spaceship/sprites/asteroid_size_3.png").unwrap(),
-                Vec2::new(15.0, 15.0),
-            ),
-        ]
+    impl Peer {
+        /// Hosts or joins a lockstep game when asked to on the command line, with
+        /// `--lockstep-host [address]` or `--lockstep-connect [address]`. The host steers the
+        /// spaceship, and the player that connects shoots.
+        fn from_args() -> Result<Option<Self>, String> {
+            let mut args = env::args().skip(1);
+            let flag = match args.next() {
+                Some(flag) => flag,
+                None => return Ok(None),
+            };
+            let address = args
+                .next()
+                .unwrap_or_else(|| net::DEFAULT_ADDRESS.to_string());
+
+            match flag.as_str() {
+                "--lockstep-host" => {
+                    let listener = TcpListener::bind(&address)
+                        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+                    log::info!("Waiting for a player to connect to {}", address);
+                    let mut connection = Connection::accept(&listener)
+                        .map_err(|e| format!("Failed to accept a player: {}", e))?;
+
+                    let seed = rand::random();
+                    connection
+                        .send(&Message::Start { seed })
+                        .map_err(|e| format!("Failed to start the game: {}", e))?;
+                    random::seed(seed);
+                    Ok(Some(Peer {
+                        connection,
+                        lockstep: Lockstep::default(),
+                        player: 0,
+                    }))
+                }
+                "--lockstep-connect" => {
+                    let mut connection = Connection::connect(&address)
+                        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+
+                    // The host may already have sent its first tick along with the seed
+                    let mut lockstep = Lockstep::default();
+                    let mut seed = None;
+                    while seed.is_none() {
+                        for message in connection.receive()? {
+                            match message {
+                                Message::Start { seed: start } => seed = Some(start),
+                                message => lockstep.receive(message),
+                            }
+                        }
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    random::seed(seed.unwrap());
+                    Ok(Some(Peer {
+                        connection,
+                        lockstep,
+                        player: 1,
+                    }))
+                }
+                flag => Err(format!(
+                    "Unknown argument '{}', expected --lockstep-host or --lockstep-connect",
+                    flag
+                )),
+            }
+        }
+
+        /// Exchanges `input` with the other instance, and runs a tick of `game` once the input of
+        /// both players is known. Returns whether a tick ran.
+        fn tick(&mut self, game: &mut Spaceship, input: Snapshot) -> Result<bool, String> {
+            self.lockstep.set_input(|| input, || game.snapshot());
+            for message in self.connection.receive()? {
+                self.lockstep.receive(message);
+            }
+            for message in self.lockstep.outgoing() {
+                self.connection
+                    .send(&message)
+                    .map_err(|e| format!("Failed to send input: {}", e))?;
+            }
+            if let Some(desync) = self.lockstep.desync() {
+                return Err(desync.to_string());
+            }
+
+            // Wait for the other instance's input, before running the tick
+            match self.lockstep.advance() {
+                Some((mut input, remote)) => {
+                    let other = PLAYER_INPUTS[1 - self.player];
+                    input.fields.extend(
+                        remote
+                            .fields
+                            .into_iter()
+                            .filter(|(field, _)| other.contains(&field.as_str())),
+                    );
+                    game.tick(&input)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+    }
+
+    /// Returns the state of the keys for the `fields` of `PlayerInput`.
+    fn keyboard_input(ctx: &Context, fields: &[&str]) -> Snapshot {
+        Snapshot {
+            fields: KEYS
+                .iter()
+                .filter(|(field, _)| fields.contains(field))
+                .map(|&(field, key)| {
+                    let pressed = input::is_key_down(ctx, key);
+                    (field.to_string(), Value::Bool(pressed))
+                })
+                .collect(),
+        }
     }
 
     struct SpaceshipGame {
-        mun_runtime: Rc<RefCell<mun_runtime::Runtime>>,
-        asteroids: Vec<RootedStruct>,
-        rockets: Vec<RootedStruct>,
-        textures: [(Texture, Vec2<f32>); 5],
+        game: Spaceship,
+        /// The other instance of a lockstep game, if this is one.
+        peer: Option<Peer>,
+        assets: Assets,
         scaler: ScreenScaler,
-        game_struct: RootedStruct,
-        player_input: RootedStruct,
-        font: Font,
-        score: u8,
+        /// The ids of the sprites that Mun draws, by their name.
+        sprites: RootedStruct,
+        /// The colors that Mun draws with, by their name in the theme.
+        colors: RootedStruct,
+        overlay: DebugOverlay,
+        debug_draw: DebugDraw,
+        functions: EntityFunctions,
+        particles: ParticleSystem,
+        audio: Audio,
+        music: Option<SoundInstance>,
+        /// A white pixel, which all particles are drawn with so tetra draws them in a single batch.
+        particle_texture: Texture,
+        /// The states before the last frames, oldest first.
+        history: VecDeque<SavedSpaceship>,
+        /// The poses of all objects after the last two ticks, by their object's address.
+        interpolator: Interpolator<usize>,
+        /// The Mun `Pose` structs that objects are drawn at, one for each object.
+        poses: Vec<RootedStruct>,
+    }
+
+    impl SpaceshipGame {
+        /// Looks up the ids of the sprites in the `Sprites` struct, and the colors in the
+        /// `Colors` struct, after the assets or the Mun code changed.
+        fn resolve_assets(&self) {
+            if let Err(e) = self
+                .assets
+                .resolve_sprites(&mut self.sprites.by_ref().clone())
+            {
+                log::error!("Failed to resolve sprites: {}", e);
+            }
+            if let Err(e) = self.assets.theme.apply(&mut self.colors.by_ref().clone()) {
+                log::error!("Failed to apply the theme: {}", e);
+            }
+        }
+
+        /// Records the poses of all objects after a tick, to draw them between the last two ticks.
+        fn record_poses(&mut self) {
+            self.interpolator.begin_tick();
+            for object in objects(&self.game) {
+                self.interpolator
+                    .record(interpolation::object_key(&object), pose(&object));
+            }
+        }
+
+        /// Steps back one frame while <kbd>R</kbd> is held, and returns whether it did.
+        fn rewind(&mut self, ctx: &mut Context) -> bool {
+            if !input::is_key_down(ctx, Key::R) {
+                self.history.push_back(self.game.save());
+                if self.history.len() > REWIND_FRAMES {
+                    self.history.pop_front();
+                }
+                return false;
+            }
+
+            if let Some(saved) = self.history.pop_back() {
+                if let Err(e) = self.game.restore(&saved) {
+                    log::warn!("Failed to rewind: {}", e);
+                    self.history.clear();
+                }
+            }
+            true
+        }
+
+        /// Handles the debug overlay's controls for selecting and tweaking fields.
+        fn tweak_game_struct(&mut self, ctx: &mut Context) {
+            let mut game_struct = self.game.game_struct.by_ref().clone();
+
+            if input::is_key_pressed(ctx, Key::LeftBracket) {
+                self.overlay.select_previous(&game_struct);
+            }
+            if input::is_key_pressed(ctx, Key::RightBracket) {
+                self.overlay.select_next(&game_struct);
+            }
+
+            let fine =
+                input::is_key_down(ctx, Key::LeftShift) || input::is_key_down(ctx, Key::RightShift);
+            let mut steps = 0;
+            if input::is_key_pressed(ctx, Key::Minus) {
+                steps -= 1;
+            }
+            if input::is_key_pressed(ctx, Key::Equals) {
+                steps += 1;
+            }
+            if steps != 0 {
+                if let Err(e) = self.overlay.adjust_selected(&mut game_struct, steps, fine) {
+                    log::warn!("Failed to tweak `GameStruct`: {}", e);
+                }
+            }
+        }
     }
 
     impl State for SpaceshipGame {
@@ -93,349 +476,471 @@ mod spaceship {
 
             graphics::clear(ctx, Color::BLACK);
 
-            let spaceship_object: StructRef = self
-                .game_struct
-                .by_ref()
-                .get::<StructRef>("spaceship")
-                .unwrap()
-                .get::<StructRef>("object")
-                .unwrap();
-            let spaceship_object_position = spaceship_object.get::<StructRef>("position").unwrap();
-
-            // Draw rockets
-            for rocket in self.rockets.iter() {
-                let rocket_object = rocket.by_ref().get::<StructRef>("object").unwrap();
-                let rocket_position = rocket_object.get::<StructRef>("position").unwrap();
-
-                self.textures[1].0.draw(
+            for particle in self.particles.particles() {
+                self.particle_texture.draw(
                     ctx,
                     DrawParams::new()
-                        .position(Vec2::new(
-                            rocket_position.get("x").unwrap(),
-                            rocket_position.get("y").unwrap(),
-                        ))
-                        .origin(self.textures[1].1)
-                        .rotation(rocket_object.get::<f32>("angle").unwrap().to_radians()),
+                        .position(Vec2::from(particle.position))
+                        .origin(Vec2::new(0.5, 0.5))
+                        .scale(Vec2::broadcast(particle.size))
+                        .color(to_color(particle.color())),
                 );
             }
 
-            // Draw spaceship
-            self.textures[0].0.draw(
-                ctx,
-                DrawParams::new()
-                    .position(Vec2::new(
-                        spaceship_object_position.get("x").unwrap(),
-                        spaceship_object_position.get("y").unwrap(),
-                    ))
-                    .origin(self.textures[0].1)
-                    .rotation(spaceship_object.get::<f32>("angle").unwrap().to_radians()),
-            );
+            // Draw all objects between the last two ticks, in the order of `objects`: the
+            // spaceship, the rockets and the asteroids, each with a pose struct of its own
+            let alpha = time::get_blend_factor(ctx);
+            let objects = objects(&self.game);
+            let drawn: Vec<Pose> = objects
+                .iter()
+                .map(|object| {
+                    let key = interpolation::object_key(object);
+                    self.interpolator
+                        .pose(&key, alpha)
+                        .unwrap_or_else(|| pose(object))
+                })
+                .collect();
+            while self.poses.len() < drawn.len() {
+                let runtime_ref = self.game.runtime().borrow();
+                let pose: StructRef = invoke!(&runtime_ref, "new_pose").unwrap();
+                self.poses.push(pose.root(self.game.runtime().clone()));
+            }
+            for (pose, pose_struct) in drawn.iter().zip(self.poses.iter()) {
+                set_pose(&mut pose_struct.by_ref().clone(), *pose);
+            }
 
-            // Draw asteroids
-            for asteroid in self.asteroids.iter() {
-                let asteroid_object = asteroid.by_ref().get::<StructRef>("object").unwrap();
-                let asteroid_position = asteroid_object.get::<StructRef>("position").unwrap();
-                let asteroid_size: usize = asteroid.by_ref().get::<u8>("size").unwrap().into();
+            // Let Mun fill the render list, and draw it
+            {
+                let runtime_ref = self.game.runtime().borrow();
+                let sprites = self.sprites.by_ref();
+                let colors = self.colors.by_ref();
+                let (spaceship_pose, poses) = self.poses.split_first().unwrap();
+                let (rocket_poses, asteroid_poses) = poses.split_at(self.game.rockets.len());
+                self.functions
+                    .cache
+                    .for_each3(
+                        self.functions.draw_rocket,
+                        &runtime_ref,
+                        self.game
+                            .rockets
+                            .iter()
+                            .zip(rocket_poses)
+                            .map(|(rocket, pose)| {
+                                (
+                                    rocket.by_ref().clone(),
+                                    sprites.clone(),
+                                    pose.by_ref().clone(),
+                                )
+                            }),
+                    )
+                    .unwrap();
+                self.functions
+                    .cache
+                    .for_each3(
+                        self.functions.draw_asteroid,
+                        &runtime_ref,
+                        self.game
+                            .asteroids
+                            .iter()
+                            .zip(asteroid_poses)
+                            .map(|(asteroid, pose)| {
+                                (
+                                    asteroid.by_ref().clone(),
+                                    sprites.clone(),
+                                    pose.by_ref().clone(),
+                                )
+                            }),
+                    )
+                    .unwrap();
+                let _: () = invoke!(
+                    &runtime_ref,
+                    "draw",
+                    self.game.game_struct.by_ref().clone(),
+                    u32::from(self.game.score),
+                    sprites.clone(),
+                    colors.clone(),
+                    spaceship_pose.by_ref().clone()
+                )
+                .unwrap();
+            }
+            draw_commands(ctx, &self.assets, &render_list::take())?;
 
-                self.textures[asteroid_size + 1].0.draw(
-                    ctx,
-                    DrawParams::new()
-                        .position(Vec2::new(
-                            asteroid_position.get("x").unwrap(),
-                            asteroid_position.get("y").unwrap(),
-                        ))
-                        .origin(self.textures[asteroid_size + 1].1)
-                        .rotation(asteroid_object.get::<f32>("angle").unwrap().to_radians()),
-                );
+            // Draw colliders, velocities and sprite origins
+            if self.debug_draw.is_enabled() {
+                self.debug_draw.clear();
+                for (object, pose) in objects.iter().zip(drawn) {
+                    collect_debug_shapes(&mut self.debug_draw, object, pose);
+                }
+                draw_debug_shapes(ctx, &self.debug_draw)?;
             }
 
             graphics::reset_canvas(ctx);
 
             self.scaler.draw(ctx);
 
-            // Draw score
-            &Text::new(format!("Score {}", self.score), self.font.clone())
-                .draw(ctx, Vec2::new(10., 10.));
+            // Draw debug overlay
+            if let (true, Some(font)) = (self.overlay.is_visible(), self.assets.font("debug")) {
+                let lines = self.overlay.lines(&self.game.game_struct.by_ref());
+                Text::new(lines.join("\n"), font.clone()).draw(
+                    ctx,
+                    DrawParams::new()
+                        .position(Vec2::new(10., 40.))
+                        .color(OVERLAY_COLOR),
+                );
+            }
 
             Ok(())
         }
 
         fn update(&mut self, ctx: &mut Context) -> tetra::Result {
-            // Collect input to pass it into mun runtime
-            let player_input = self.player_input.by_ref().clone();
-            let player_input = player_input.root(self.mun_runtime.clone());
-            let mut player_input = player_input.by_ref().clone();
+            profiler::end_frame();
+            if self.assets.reload_if_changed(ctx) {
+                // Restart the music, and look up the sprites and colors, which may have changed
+                self.audio.reset_music();
+                self.resolve_assets();
+            }
 
-            if input::is_key_down(ctx, Key::Left) {
-                player_input.set("left", true).unwrap();
+            if input::is_key_pressed(ctx, Key::F1) {
+                self.overlay.toggle();
             }
-            if input::is_key_down(ctx, Key::Right) {
-                player_input.set("right", true).unwrap();
+            if input::is_key_pressed(ctx, Key::F2) {
+                self.debug_draw.toggle();
             }
-            if input::is_key_down(ctx, Key::Up) {
-                player_input.set("up", true).unwrap();
+            if input::is_key_pressed(ctx, Key::F3) {
+                self.overlay.toggle_profile();
             }
-            if input::is_key_down(ctx, Key::Z) {
-                player_input.set("shoot", true).unwrap();
+            if input::is_key_pressed(ctx, Key::F4) {
+                dump_profile();
             }
-
-            let runtime_ref = self.mun_runtime.borrow();
-            let mut game_struct = self.game_struct.by_ref().clone();
-
-            if game_struct.get::<bool>("spawn_new_rocket").unwrap() {
-                game_struct.set("spawn_new_rocket", false).unwrap();
-
-                if !(self.rockets.len() >= invoke_fn!(&runtime_ref, "max_rockets_amount").unwrap())
-                {
-                    let spaceship_object: StructRef = game_struct
-                        .get::<StructRef>("spaceship")
-                        .unwrap()
-                        .get::<StructRef>("object")
-                        .unwrap();
-                    let spaceship_positon = spaceship_object.get::<StructRef>("position").unwrap();
-
-                    let new_bullet: StructRef = invoke_fn!(
-                        &runtime_ref,
-                        "new_rocket",
-                        spaceship_positon,
-                        spaceship_object.get::<f32>("angle").unwrap()
-                    )
-                    .unwrap();
-
-                    self.rockets.push(new_bullet.root(self.mun_runtime.clone()));
-                }
+            if self.overlay.is_state_visible() {
+                self.tweak_game_struct(ctx);
+            }
+            // Rewinding one instance of a lockstep game would desync it
+            if self.peer.is_none() && self.rewind(ctx) {
+                self.record_poses();
+                self.particles.update();
+                return Ok(());
             }
 
-            if game_struct.get::<bool>("spawn_new_asteroids").unwrap() {
-                game_struct.set("spawn_new_asteroids", false).unwrap();
+            // Run a tick with the player's input
+            let ticked = match &mut self.peer {
+                Some(peer) => {
+                    let input = keyboard_input(ctx, PLAYER_INPUTS[peer.player]);
+                    peer.tick(&mut self.game, input)
+                        .map_err(TetraError::PlatformError)?
+                }
+                None => {
+                    let fields: Vec<&str> = KEYS.iter().map(|&(field, _)| field).collect();
+                    self.game
+                        .tick(&keyboard_input(ctx, &fields))
+                        .map_err(TetraError::PlatformError)?;
+                    true
+                }
+            };
 
-                self.asteroids = new_asteroids(&self.mun_runtime);
+            if self.game.update() {
+                // Reloading replaced the functions that were cached, and may have changed the
+                // layout of the saved structs
+                self.functions.cache.invalidate();
+                self.history.clear();
+                self.interpolator.clear();
+                // Fields added to `Sprites` or `Colors` are only set when they are looked up
+                self.resolve_assets();
             }
-
-            // Rockets update
-            for index in 0..self.rockets.len() {
-                let _: () = invoke_fn!(
-                    &runtime_ref,
-                    "update_rocket",
-                    self.rockets[index].by_ref().clone()
-                )
-                .unwrap();
+            if ticked {
+                self.record_poses();
             }
-            // Delete rockets
-            self.rockets
-                .retain(|rocket| !rocket.by_ref().get::<bool>("need_to_destroy").unwrap());
 
-            // Asteroids update
-            for index in 0..self.asteroids.len() {
-                let _: () = invoke_fn!(
-                    &runtime_ref,
-                    "update_asteroid",
-                    self.asteroids[index].by_ref().clone()
-                )
-                .unwrap();
+            // Emit particles for the effects that Mun requested, and move all particles
+            for effect in particles::take_effects() {
+                match effect {
+                    Effect::Explosion { position, size } => {
+                        self.particles.emit(&explosion_emitter(size), position)
+                    }
+                    Effect::Thrust { position, angle } => self.particles.emit(
+                        &Emitter {
+                            direction: angle + 180.0,
+                            ..THRUST_EMITTER
+                        },
+                        position,
+                    ),
+                }
             }
+            self.particles.update();
 
-            let mut new_asteroids: Vec<StructRef> = Vec::new();
-
-            let mut asteroids: Vec<RootedStruct> = self
-                .asteroids
-                .iter()
-                .map(|asteroid| {
-                    let asteroid = asteroid.by_ref().clone();
-                    asteroid.root(self.mun_runtime.clone())
-                })
-                .collect();
+            self.audio.play_requests(&mut TetraAudio {
+                ctx,
+                sounds: &self.assets.sounds,
+                music: &mut self.music,
+            });
 
-            asteroids.retain(|asteroid| {
-                if asteroid.by_ref().get::<bool>("need_to_destroy").unwrap() {
-                    if asteroid.by_ref().get::<u8>("size").unwrap() > 1 {
-                        let asteroid_object = asteroid.by_ref().get::<StructRef>("object").unwrap();
-
-                        new_asteroids.push(
-                            invoke_fn!(
-                                &runtime_ref,
-                                "new_asteroid",
-                                asteroid_object.get::<StructRef>("position").unwrap(),
-                                thread_rng().gen_range(0.0_f32..360.0_f32),
-                                asteroid.by_ref().get::<u8>("size").unwrap() - 1
-                            )
-                            .unwrap(),
-                        );
+            self.overlay
+                .set_counter("FPS", format!("{:.0}", time::get_fps(ctx)));
+            self.overlay
+                .set_counter("Asteroids", self.game.asteroids.len());
+            self.overlay.set_counter("Rockets", self.game.rockets.len());
+            self.overlay.set_counter("Score", self.game.score);
 
-                        new_asteroids.push(
-                            invoke_fn!(
-                                &runtime_ref,
-                                "new_asteroid",
-                                asteroid_object.get::<StructRef>("position").unwrap(),
-                                thread_rng().gen_range(0.0_f32..360.0_f32),
-                                asteroid.by_ref().get::<u8>("size").unwrap() - 1
-                            )
-                            .unwrap(),
-                        );
-                    }
-                    false
-                } else {
-                    true
-                }
-            });
+            Ok(())
+        }
+    }
 
-            asteroids.append(
-                &mut new_asteroids
-                    .into_iter()
-                    .map(|elem| elem.root(self.mun_runtime.clone()))
-                    .collect(),
-            );
+    /// Returns the `Object`s of the spaceship, rockets and asteroids.
+    fn objects(game: &Spaceship) -> Vec<StructRef<'_>> {
+        let spaceship = game
+            .game_struct
+            .by_ref()
+            .get::<StructRef>("spaceship")
+            .unwrap();
+        iter::once(spaceship)
+            .chain(game.rockets.iter().map(|rocket| rocket.by_ref().clone()))
+            .chain(
+                game.asteroids
+                    .iter()
+                    .map(|asteroid| asteroid.by_ref().clone()),
+            )
+            .map(|entity| entity.get::<StructRef>("object").unwrap())
+            .collect()
+    }
 
-            self.asteroids = asteroids;
+    fn pose(object: &StructRef) -> Pose {
+        let position = object.get::<StructRef>("position").unwrap();
+        Pose {
+            position: [position.get("x").unwrap(), position.get("y").unwrap()],
+            angle: object.get("angle").unwrap(),
+        }
+    }
 
-            // Asteroids and rocket collision
-            for rocket in self.rockets.iter_mut() {
-                for asteroid in self.asteroids.iter_mut() {
-                    let mut rocket = rocket.by_ref().clone();
-                    let mut asteroid = asteroid.by_ref().clone();
+    /// Sets the fields of a Mun `Pose` struct, where Mun draws an object.
+    fn set_pose(pose_struct: &mut StructRef, pose: Pose) {
+        pose_struct.set("x", pose.position[0]).unwrap();
+        pose_struct.set("y", pose.position[1]).unwrap();
+        pose_struct.set("angle", pose.angle).unwrap();
+    }
 
-                    let collide: bool = invoke_fn!(
-                        &runtime_ref,
-                        "object_collide",
-                        rocket.get::<StructRef>("object").clone().unwrap(),
-                        asteroid.get::<StructRef>("object").clone().unwrap()
-                    )
-                    .unwrap();
+    fn to_color(color: theme::Color) -> Color {
+        Color::rgba(color[0], color[1], color[2], color[3])
+    }
 
-                    if collide {
-                        self.score += 1;
-                        rocket.set("need_to_destroy", true).unwrap();
-                        asteroid.set("need_to_destroy", true).unwrap();
+    /// Draws the commands of a render list in order. Consecutive shapes are drawn as a single mesh.
+    fn draw_commands(
+        ctx: &mut Context,
+        assets: &Assets,
+        commands: &[DrawCommand],
+    ) -> tetra::Result {
+        let mut shapes: Option<GeometryBuilder> = None;
+        for command in commands {
+            match *command {
+                DrawCommand::Rect {
+                    position,
+                    width,
+                    height,
+                    color,
+                } => {
+                    shapes
+                        .get_or_insert_with(GeometryBuilder::new)
+                        .set_color(to_color(color))
+                        .rectangle(
+                            ShapeStyle::Fill,
+                            Rectangle::new(position[0], position[1], width, height),
+                        )?;
+                }
+                DrawCommand::Circle {
+                    center,
+                    radius,
+                    color,
+                } => {
+                    shapes
+                        .get_or_insert_with(GeometryBuilder::new)
+                        .set_color(to_color(color))
+                        .circle(ShapeStyle::Fill, Vec2::from(center), radius)?;
+                }
+                DrawCommand::Sprite {
+                    id,
+                    position,
+                    rotation,
+                    scale,
+                    color,
+                } => {
+                    draw_shapes(ctx, &mut shapes)?;
+                    if let Some((texture, origin)) = assets.sprite(id) {
+                        texture.draw(
+                            ctx,
+                            DrawParams::new()
+                                .position(Vec2::from(position))
+                                .origin(*origin)
+                                .rotation(rotation.to_radians())
+                                .scale(Vec2::from(scale))
+                                .color(to_color(color)),
+                        );
                     }
                 }
-            }
-
-            // Asteroids and spaceship collision
-            for asteroid in self.asteroids.iter() {
-                let collide: bool = invoke_fn!(
-                    runtime_ref,
-                    "object_collide",
-                    game_struct
-                        .get::<StructRef>("spaceship")
-                        .unwrap()
-                        .get::<StructRef>("object")
-                        .unwrap(),
-                    asteroid
-                        .by_ref()
-                        .get::<StructRef>("object")
-                        .clone()
-                        .unwrap()
-                )
-                .unwrap();
-
-                if collide {
-                    game_struct
-                        .set("token", rand::thread_rng().gen::<u8>())
-                        .unwrap();
-
-                    self.rockets.clear();
-
-                    self.score = 0;
+                DrawCommand::Number {
+                    value,
+                    position,
+                    color,
+                } => {
+                    draw_shapes(ctx, &mut shapes)?;
+                    draw_text(ctx, assets, &value.to_string(), position, color);
+                }
+                DrawCommand::Text {
+                    id,
+                    position,
+                    color,
+                } => {
+                    draw_shapes(ctx, &mut shapes)?;
+                    if let Some(text) = assets.manifest.text(id) {
+                        draw_text(ctx, assets, text, position, color);
+                    }
                 }
             }
+        }
+        draw_shapes(ctx, &mut shapes)
+    }
 
-            if self.asteroids.is_empty() {
-                game_struct.set("spawn_new_asteroids", true).unwrap();
-            }
+    /// Draws `text` with the score font, with its top-left corner at `position`.
+    fn draw_text(
+        ctx: &mut Context,
+        assets: &Assets,
+        text: &str,
+        position: [f32; 2],
+        color: theme::Color,
+    ) {
+        if let Some(font) = assets.font("score") {
+            Text::new(text, font.clone()).draw(
+                ctx,
+                DrawParams::new()
+                    .position(Vec2::from(position))
+                    .color(to_color(color)),
+            );
+        }
+    }
 
-            let _: () =
-                invoke_fn!(&runtime_ref, "update", game_struct, player_input.clone()).unwrap();
+    /// Draws and clears the shapes collected by [`draw_commands`].
+    fn draw_shapes(ctx: &mut Context, shapes: &mut Option<GeometryBuilder>) -> tetra::Result {
+        if let Some(builder) = shapes.take() {
+            builder.build_mesh(ctx)?.draw(ctx, DrawParams::new());
+        }
+        Ok(())
+    }
 
-            // Drop shared refernce to the runtime so we can borrow it mutably
-            drop(runtime_ref);
-            self.mun_runtime.borrow_mut().update();
+    fn dump_profile() {
+        match profiler::write_report(PROFILE_REPORT_PATH) {
+            Ok(()) => log::info!("Wrote Mun profile to {}", PROFILE_REPORT_PATH),
+            Err(e) => log::error!("Failed to write Mun profile: {}", e),
+        }
+    }
 
-            let runtime_ref = self.mun_runtime.borrow();
-            let new_player_input: StructRef = invoke_fn!(&runtime_ref, "new_player_input").unwrap();
-            self.player_input = new_player_input.root(self.mun_runtime.clone());
+    /// Collects the shapes of `object`, at the `pose` it is drawn at.
+    fn collect_debug_shapes(debug_draw: &mut DebugDraw, object: &StructRef, pose: Pose) {
+        let position = pose.position;
+        let velocity = debug_draw::heading(pose.angle, object.get("speed").unwrap());
 
-            Ok(())
+        let radius = object.get("radius").unwrap();
+        for image in wrap::images(position, radius, game_area_width(), game_area_height()) {
+            debug_draw.circle(Layer::Collider, image, radius);
         }
+        debug_draw.arrow(
+            Layer::Velocity,
+            position,
+            [
+                position[0] + velocity[0] * VELOCITY_ARROW_FRAMES,
+                position[1] + velocity[1] * VELOCITY_ARROW_FRAMES,
+            ],
+        );
+        debug_draw.cross(Layer::Origin, position, 4.0);
     }
 
-    fn new_asteroids(mun_runtime: &Rc<RefCell<mun_runtime::Runtime>>) -> Vec<RootedStruct> {
-        let runtime_ref = mun_runtime.borrow();
-        let mut asteroids = Vec::new();
-        for _ in 0..invoke_fn!(&runtime_ref, "initial_asteroids_amount").unwrap() {
-            let position: (f32, f32) = {
-                if thread_rng().gen_range(0..1) == 0 {
-                    (0.0, thread_rng().gen_range(0.0..game_area_height()))
-                } else {
-                    (0.0, thread_rng().gen_range(game_area_width()..0.0))
+    fn draw_debug_shapes(ctx: &mut Context, debug_draw: &DebugDraw) -> tetra::Result {
+        if debug_draw.shapes().is_empty() {
+            return Ok(());
+        }
+
+        let mut builder = GeometryBuilder::new();
+        for (layer, shape) in debug_draw.shapes() {
+            builder.set_color(match layer {
+                Layer::Collider => Color::rgb(1.0, 0.2, 0.2),
+                Layer::Velocity => Color::rgb(0.2, 0.6, 1.0),
+                Layer::Origin => Color::rgb(1.0, 1.0, 0.2),
+            });
+            match *shape {
+                Shape::Circle { center, radius } => {
+                    builder.circle(ShapeStyle::Stroke(1.0), Vec2::from(center), radius)?
+                }
+                Shape::Rect {
+                    position,
+                    width,
+                    height,
+                } => builder.rectangle(
+                    ShapeStyle::Stroke(1.0),
+                    Rectangle::new(position[0], position[1], width, height),
+                )?,
+                Shape::Line { from, to } => {
+                    builder.polyline(1.0, &[Vec2::from(from), Vec2::from(to)])?
                 }
             };
-
-            let asteroid_position: StructRef =
-                invoke_fn!(&runtime_ref, "new_vec2", position.0, position.1).unwrap();
-
-            let asteroid: StructRef = invoke_fn!(
-                &runtime_ref,
-                "new_asteroid",
-                asteroid_position,
-                thread_rng().gen_range(0.0_f32..360.0_f32),
-                3_u8
-            )
-            .unwrap();
-            asteroids.push(asteroid.root(mun_runtime.clone()));
         }
-        asteroids
+        builder.build_mesh(ctx)?.draw(ctx, DrawParams::new());
+        Ok(())
     }
 
-    fn main() -> tetra::Result {
-        let runtime = RuntimeBuilder::new("spaceship.munlib")
-            .insert_fn("sin", sin as extern "C" fn(number: f32) -> f32)
-            .insert_fn("cos", cos as extern "C" fn(number: f32) -> f32)
-            .insert_fn("dbg", dbg as extern "C" fn(number: f32))
-            .insert_fn(
-                "degrees_to_radians",
-                degrees_to_radians as extern "C" fn(degrees: f32) -> f32,
-            )
-            .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32)
-            .insert_fn("game_area_width", game_area_width as extern "C" fn() -> f32)
-            .insert_fn(
-                "game_area_height",
-                game_area_height as extern "C" fn() -> f32,
-            )
-            .spawn()
-            .expect("Failed to spawn Runtime");
+    pub fn main() -> tetra::Result {
+        env_logger::init();
 
-        let game_struct = {
+        // A lockstep game seeds the random numbers before the game starts
+        let peer = Peer::from_args().unwrap_or_else(|e| panic!("{}", e));
+
+        let runtime = game::register(RuntimeBuilder::new("spaceship.munlib"));
+        let runtime = audio::register(particles::register(render_list::register(
+            logging::register(runtime),
+        )))
+        .spawn()
+        .expect("Failed to spawn Runtime");
+
+        let sprites = {
             let runtime_ref = runtime.borrow();
-            let game_struct: StructRef = invoke_fn!(&runtime_ref, "new_game_struct").unwrap();
-            game_struct.root(runtime.clone())
+            let sprites: StructRef = invoke!(&runtime_ref, "new_sprites").unwrap();
+            sprites.root(runtime.clone())
         };
 
-        let player_input = {
+        let colors = {
             let runtime_ref = runtime.borrow();
-            let player_input: StructRef = invoke_fn!(&runtime_ref, "new_player_input").unwrap();
-            player_input.root(runtime.clone())
+            let colors: StructRef = invoke!(&runtime_ref, "new_colors").unwrap();
+            colors.root(runtime.clone())
         };
 
         ContextBuilder::new("Spaceship Game", 1280, 720)
             .build()?
             .run(|ctx| {
-                Ok(SpaceshipGame {
-                    mun_runtime: runtime,
-                    asteroids: Vec::new(),
-                    rockets: Vec::new(),
+                let game = SpaceshipGame {
+                    game: Spaceship::new(runtime).map_err(TetraError::PlatformError)?,
+                    peer,
                     scaler: ScreenScaler::with_window_size(
                         ctx,
                         game_area_width() as i32,
                         game_area_height() as i32,
                         ScalingMode::ShowAllPixelPerfect,
                     )?,
-                    textures: textures(ctx),
-                    game_struct: game_struct,
-                    player_input: player_input,
-                    font: Font::vector(ctx, "./assets/spaceship/fonts/Minimal3x5.ttf", 18.0)
-                        .unwrap(),
-                    score: 0,
-                })
+                    assets: Assets::load(ctx).map_err(TetraError::PlatformError)?,
+                    sprites,
+                    colors,
+                    overlay: DebugOverlay::default(),
+                    debug_draw: DebugDraw::default(),
+                    functions: EntityFunctions::new(),
+                    particles: ParticleSystem::default(),
+                    audio: Audio::default(),
+                    music: None,
+                    particle_texture: Texture::from_rgba(ctx, 1, 1, &[255; 4])?,
+                    history: VecDeque::new(),
+                    interpolator: Interpolator::wrapping(
+                        game_area_width(),
+                        game_area_height(),
+                        MAX_TICK_DISTANCE,
+                    ),
+                    poses: Vec::new(),
+                };
+                game.resolve_assets();
+                Ok(game)
             })
     }
 }
@@ -0,0 +1,238 @@
+//! A data-driven list of the assets a game uses, and detection of changes to them so they can be
+//! hot reloaded like the Mun code.
+//!
+//! A manifest lists one asset per line, with paths relative to the manifest:
+//!
+//! ```text
+//! // kind     name       path                   origin x, y / size
+//! sprite 1    rocket     sprites/rocket.png     3 3
+//! font        score      fonts/Minimal3x5.ttf   18
+//! sound 0     shoot      sounds/shoot.wav
+//! text 0      game_over  Game over
+//! ```
+//!
+//! Besides its name, every sprite, sound and text has a numeric id, which Mun code can use to refer
+//! to it because Mun doesn't have strings. A host can also look sprites up by name, and pass their
+//! ids to Mun in the fields of a struct that are named after them. Sprites, sounds and texts are
+//! numbered separately. Instead of a path, a text is followed by the words it consists of, which
+//! are separated by single spaces. Empty lines and lines starting with `//` are ignored.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
+
+/// A texture, and the point within it that is placed at an entity's position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteEntry {
+    pub id: u32,
+    pub name: String,
+    pub path: PathBuf,
+    pub origin: [f32; 2],
+}
+
+/// A vector font, and the size to rasterize it at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: f32,
+}
+
+/// A sound effect or piece of music.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundEntry {
+    pub id: u32,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// A piece of text that Mun code can draw.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEntry {
+    pub id: u32,
+    pub name: String,
+    pub text: String,
+}
+
+/// The sprites, fonts, sounds and texts listed by a manifest file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub sprites: Vec<SpriteEntry>,
+    pub fonts: Vec<FontEntry>,
+    pub sounds: Vec<SoundEntry>,
+    pub texts: Vec<TextEntry>,
+}
+
+fn parse_number<T: FromStr>(value: Option<&str>, what: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing {}", what))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", what, value))
+}
+
+impl Manifest {
+    /// Parses the contents of a manifest, resolving paths relative to `base`.
+    pub fn parse(source: &str, base: &Path) -> Result<Self, String> {
+        let mut manifest = Manifest::default();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let error = |e: String| format!("line {}: {}", index + 1, e);
+            let mut words = line.split_whitespace();
+            let kind = words.next().unwrap();
+            let id = match kind {
+                "sprite" | "sound" | "text" => {
+                    Some(parse_number(words.next(), &format!("{} id", kind)).map_err(error)?)
+                }
+                _ => None,
+            };
+            let name = words
+                .next()
+                .ok_or_else(|| error("missing name".to_string()))?
+                .to_string();
+            if manifest.contains(&name) {
+                return Err(error(format!("duplicate asset '{}'", name)));
+            }
+
+            if let ("text", Some(id)) = (kind, id) {
+                if manifest.texts.iter().any(|text| text.id == id) {
+                    return Err(error(format!("duplicate text id {}", id)));
+                }
+                let text = words.collect::<Vec<_>>().join(" ");
+                if text.is_empty() {
+                    return Err(error("missing text".to_string()));
+                }
+                manifest.texts.push(TextEntry { id, name, text });
+                continue;
+            }
+
+            let path = base.join(
+                words
+                    .next()
+                    .ok_or_else(|| error("missing path".to_string()))?,
+            );
+
+            match (kind, id) {
+                ("sprite", Some(id)) => {
+                    if manifest.sprites.iter().any(|sprite| sprite.id == id) {
+                        return Err(error(format!("duplicate sprite id {}", id)));
+                    }
+                    manifest.sprites.push(SpriteEntry {
+                        id,
+                        name,
+                        path,
+                        origin: [
+                            parse_number(words.next(), "origin x").map_err(error)?,
+                            parse_number(words.next(), "origin y").map_err(error)?,
+                        ],
+                    })
+                }
+                ("font", _) => manifest.fonts.push(FontEntry {
+                    name,
+                    path,
+                    size: parse_number(words.next(), "font size").map_err(error)?,
+                }),
+                ("sound", Some(id)) => {
+                    if manifest.sounds.iter().any(|sound| sound.id == id) {
+                        return Err(error(format!("duplicate sound id {}", id)));
+                    }
+                    manifest.sounds.push(SoundEntry { id, name, path })
+                }
+                _ => return Err(error(format!("unknown kind of asset '{}'", kind))),
+            }
+            if let Some(word) = words.next() {
+                return Err(error(format!("unexpected '{}'", word)));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Reads and parses the manifest at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::parse(&source, base)
+            .map_err(|e| format!("Failed to parse '{}', {}", path.display(), e))
+    }
+
+    /// Returns the sprite called `name`.
+    pub fn sprite(&self, name: &str) -> Option<&SpriteEntry> {
+        self.sprites.iter().find(|sprite| sprite.name == name)
+    }
+
+    /// Returns the text with `id`.
+    pub fn text(&self, id: u32) -> Option<&str> {
+        self.texts
+            .iter()
+            .find(|text| text.id == id)
+            .map(|text| text.text.as_str())
+    }
+
+    /// Whether the manifest lists an asset called `name`.
+    fn contains(&self, name: &str) -> bool {
+        self.sprites.iter().any(|sprite| sprite.name == name)
+            || self.fonts.iter().any(|font| font.name == name)
+            || self.sounds.iter().any(|sound| sound.name == name)
+            || self.texts.iter().any(|text| text.name == name)
+    }
+
+    /// Returns the paths of all listed assets.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.sprites
+            .iter()
+            .map(|sprite| sprite.path.as_path())
+            .chain(self.fonts.iter().map(|font| font.path.as_path()))
+            .chain(self.sounds.iter().map(|sound| sound.path.as_path()))
+    }
+}
+
+/// Detects changes to a set of files by polling their modification times.
+#[derive(Default)]
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+impl FileWatcher {
+    /// Starts watching `paths`, as they are now.
+    pub fn new<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            files: paths
+                .into_iter()
+                .map(|path| {
+                    let path = path.into();
+                    let modified = modified(&path);
+                    (path, modified)
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether any of the files was modified, created or removed since the last poll.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last_modified) in self.files.iter_mut() {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,128 @@
+//! Sound effects and music that Mun code can play.
+//!
+//! Mun code requests sounds through the externs registered by [`register`], referring to them by
+//! their id in the asset manifest. Every frame, the host passes the requests to an
+//! [`AudioBackend`] through [`Audio::play_requests`]. The [`NullBackend`] plays nothing, so games
+//! can run headlessly and tests can check what would have been heard.
+//!
+//! To use them from a script, declare the externs you need:
+//!
+//! ```mun
+//! extern fn play_sound(id: u32);
+//! extern fn set_music(id: u32);
+//! ```
+
+use mun_runtime::RuntimeBuilder;
+use std::cell::RefCell;
+
+/// A sound requested by Mun code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioRequest {
+    /// Play the sound with `id` once.
+    PlaySound(u32),
+    /// Play the sound with `id` on repeat, instead of the current music.
+    SetMusic(u32),
+}
+
+thread_local! {
+    static REQUESTS: RefCell<Vec<AudioRequest>> = const { RefCell::new(Vec::new()) };
+}
+
+fn push(request: AudioRequest) {
+    REQUESTS.with(|requests| requests.borrow_mut().push(request));
+}
+
+/// Plays the sound with `id` once.
+pub extern "C" fn play_sound(id: u32) {
+    push(AudioRequest::PlaySound(id));
+}
+
+/// Plays the sound with `id` on repeat, instead of the current music. Requesting the music that is
+/// already playing doesn't restart it, so scripts can call this every frame.
+pub extern "C" fn set_music(id: u32) {
+    push(AudioRequest::SetMusic(id));
+}
+
+/// Adds all audio externs to the runtime `builder`.
+pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+    builder
+        .insert_fn("play_sound", play_sound as extern "C" fn(u32))
+        .insert_fn("set_music", set_music as extern "C" fn(u32))
+}
+
+/// Returns the requests made since the last call.
+pub fn take() -> Vec<AudioRequest> {
+    REQUESTS.with(|requests| std::mem::take(&mut *requests.borrow_mut()))
+}
+
+/// Plays sounds on behalf of [`Audio`]. Backends log, rather than return, errors, so a missing
+/// sound never stops a game.
+pub trait AudioBackend {
+    /// Plays the sound with `id` once, alongside any other sounds.
+    fn play_sound(&mut self, id: u32);
+    /// Stops the current music, and plays the sound with `id` on repeat.
+    fn start_music(&mut self, id: u32);
+}
+
+/// A backend that plays nothing, but remembers what it was asked to play.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NullBackend {
+    /// The ids of the sounds that were played, in order.
+    pub sounds: Vec<u32>,
+    /// The ids of the music that was started, in order.
+    pub music: Vec<u32>,
+}
+
+impl AudioBackend for NullBackend {
+    fn play_sound(&mut self, id: u32) {
+        self.sounds.push(id);
+    }
+
+    fn start_music(&mut self, id: u32) {
+        self.music.push(id);
+    }
+}
+
+/// Keeps track of the music that is playing.
+#[derive(Clone, Debug, Default)]
+pub struct Audio {
+    music: Option<u32>,
+}
+
+impl Audio {
+    /// Plays the sounds requested since the last call on `backend`. The music only changes when
+    /// Mun asks for a different piece.
+    pub fn play_requests(&mut self, backend: &mut impl AudioBackend) {
+        self.play(take(), backend);
+    }
+
+    /// Plays `requests` that were taken elsewhere, e.g. on another thread, on `backend`.
+    pub fn play(
+        &mut self,
+        requests: impl IntoIterator<Item = AudioRequest>,
+        backend: &mut impl AudioBackend,
+    ) {
+        for request in requests {
+            match request {
+                AudioRequest::PlaySound(id) => backend.play_sound(id),
+                AudioRequest::SetMusic(id) => {
+                    if self.music != Some(id) {
+                        self.music = Some(id);
+                        backend.start_music(id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the id of the music that is playing.
+    pub fn music(&self) -> Option<u32> {
+        self.music
+    }
+
+    /// Forgets the music that is playing, so the next request starts it again, for example after
+    /// the sounds were reloaded.
+    pub fn reset_music(&mut self) {
+        self.music = None;
+    }
+}
@@ -0,0 +1,220 @@
+//! Running the Mun simulation of a game on a thread of its own, while another thread renders it.
+//!
+//! A Mun runtime can't be shared between threads, so [`BackgroundSimulation::spawn`] loads the
+//! munlib on the simulation thread, and only copies of the state cross over. Every tick, the
+//! simulation thread applies the latest input that it was sent, invokes the update function, and
+//! sends a [`Frame`] with a [`Snapshot`] of the state. The render thread records the poses of the
+//! objects in every frame with an [`Interpolator`](crate::interpolation::Interpolator), and draws
+//! them between the last two frames, [`alpha`](BackgroundSimulation::alpha) of the way, so movement
+//! looks smooth at any frame rate.
+//!
+//! Hot reloads happen on the simulation thread, between two ticks. The first frame after a reload
+//! says so, as the objects in it may have changed. A render thread that runs the same munlib for
+//! drawing reloads it when it notices the change itself, so it may briefly receive states of
+//! another layout.
+
+use crate::{
+    audio::{self, AudioRequest},
+    particles::{self, Effect},
+    render_list,
+    snapshot::Snapshot,
+};
+use mun_runtime::{Runtime, StructRef};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// What a tick of the simulation produced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub tick: u64,
+    /// The state after the tick.
+    pub state: Snapshot,
+    pub audio_requests: Vec<AudioRequest>,
+    pub effects: Vec<Effect>,
+    /// Whether the munlib was reloaded since the previous tick.
+    pub reloaded: bool,
+}
+
+enum Command {
+    Input(Snapshot),
+    Stop,
+}
+
+/// The state of the latest frame, and when it arrived.
+struct Latest {
+    state: Snapshot,
+    arrived: Instant,
+}
+
+/// A game whose Mun code runs on a thread of its own.
+pub struct BackgroundSimulation {
+    commands: Sender<Command>,
+    frames: Receiver<Frame>,
+    thread: Option<JoinHandle<Result<(), String>>>,
+    tick_duration: Duration,
+    latest: Option<Latest>,
+}
+
+impl BackgroundSimulation {
+    /// Starts a simulation that ticks every `tick_duration`. On the new thread, `spawn_runtime`
+    /// loads the munlib and the Mun function `new_state` creates the state, which is passed to the
+    /// Mun function `update` every tick.
+    pub fn spawn(
+        spawn_runtime: impl FnOnce() -> Result<Rc<RefCell<Runtime>>, String> + Send + 'static,
+        new_state: &'static str,
+        update: &'static str,
+        tick_duration: Duration,
+    ) -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        let (frame_sender, frames) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let runtime = spawn_runtime()?;
+            run(
+                &runtime,
+                new_state,
+                update,
+                tick_duration,
+                &command_receiver,
+                &frame_sender,
+            )
+        });
+
+        Self {
+            commands,
+            frames,
+            thread: Some(thread),
+            tick_duration,
+            latest: None,
+        }
+    }
+
+    /// Sets fields of the state before every following tick, e.g. `paddle_left.move_up`.
+    pub fn set_input(&self, input: Snapshot) {
+        // A simulation that stopped reports why from `receive`
+        let _ = self.commands.send(Command::Input(input));
+    }
+
+    /// Returns the frames that arrived since the last call, oldest first, or why the simulation
+    /// stopped.
+    pub fn receive(&mut self) -> Result<Vec<Frame>, String> {
+        let mut frames = Vec::new();
+        loop {
+            match self.frames.try_recv() {
+                Ok(frame) => frames.push(frame),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !frames.is_empty() {
+                        break;
+                    }
+                    return Err(match self.thread.take().map(JoinHandle::join) {
+                        Some(Ok(Err(e))) => e,
+                        Some(Err(_)) => "The simulation panicked".to_string(),
+                        _ => "The simulation stopped".to_string(),
+                    });
+                }
+            }
+        }
+
+        if let Some(frame) = frames.last() {
+            self.latest = Some(Latest {
+                state: frame.state.clone(),
+                arrived: Instant::now(),
+            });
+        }
+        Ok(frames)
+    }
+
+    /// Returns how far rendering is from the state before the latest frame, `0.0`, to the state
+    /// of the latest frame, `1.0`, going by the time since it arrived.
+    pub fn alpha(&self) -> f32 {
+        self.latest.as_ref().map_or(1.0, |latest| {
+            (latest.arrived.elapsed().as_secs_f32() / self.tick_duration.as_secs_f32()).min(1.0)
+        })
+    }
+
+    /// Returns the state of the latest frame, or `None` before the first frame arrived.
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.latest.as_ref().map(|latest| &latest.state)
+    }
+}
+
+impl Drop for BackgroundSimulation {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Runs the simulation until it is told to stop, or the render thread is gone.
+fn run(
+    runtime: &Rc<RefCell<Runtime>>,
+    new_state: &'static str,
+    update: &'static str,
+    tick_duration: Duration,
+    commands: &Receiver<Command>,
+    frames: &Sender<Frame>,
+) -> Result<(), String> {
+    let state = {
+        let runtime_ref = runtime.borrow();
+        let state: Result<StructRef, _> = invoke!(&runtime_ref, new_state);
+        state
+            .map_err(|e| format!("Failed to call `{}`: {:?}", new_state, e))?
+            .root(runtime.clone())
+    };
+
+    let mut input = Snapshot::default();
+    let mut reloaded = false;
+    let mut next_tick = Instant::now();
+    for tick in 0.. {
+        loop {
+            match commands.try_recv() {
+                Ok(Command::Input(latest)) => input = latest,
+                Ok(Command::Stop) | Err(TryRecvError::Disconnected) => return Ok(()),
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        {
+            let runtime_ref = runtime.borrow();
+            let mut state = state.by_ref().clone();
+            if let Err(e) = input.restore(&mut state) {
+                log::warn!("Failed to set the input: {}", e);
+            }
+            let result: Result<(), _> = invoke!(&runtime_ref, update, state.clone());
+            result.map_err(|e| format!("Failed to call `{}`: {:?}", update, e))?;
+
+            // Nothing draws on this thread
+            render_list::take();
+            let frame = Frame {
+                tick,
+                state: Snapshot::capture(&state),
+                audio_requests: audio::take(),
+                effects: particles::take_effects(),
+                reloaded,
+            };
+            if frames.send(frame).is_err() {
+                return Ok(());
+            }
+        }
+
+        // Between ticks, nothing refers to the old code or state layout
+        reloaded = runtime.borrow_mut().update();
+
+        // Skip ticks that there was no time for, rather than catching up in a burst
+        next_tick += tick_duration;
+        let now = Instant::now();
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        } else if now - next_tick > tick_duration * 4 {
+            next_tick = now;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! A uniform grid that finds which circles might overlap, so only those pairs need an exact,
+//! and more expensive, collision test.
+//!
+//! The grid covers a toroidal world: objects that leave one edge reappear at the opposite edge,
+//! matching how `move_object` in `spaceship.mun` wraps positions. Circles that straddle an edge
+//! are stored in the cells on both sides.
+
+use std::ops::Range;
+
+/// A uniform grid of cells over a wrapping world, each listing the circles that overlap it.
+pub struct Grid {
+    width: f32,
+    height: f32,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+/// Returns the indices of the cells that the interval `[min, max]` overlaps along an axis that
+/// wraps at `extent` and is divided into `cells` cells of `cell_size`.
+///
+/// An interval that crosses the end of the axis covers two ranges of cells, otherwise the second
+/// range is empty.
+fn covered_cells(
+    min: f32,
+    max: f32,
+    extent: f32,
+    cells: usize,
+    cell_size: f32,
+) -> (Range<usize>, Range<usize>) {
+    if max - min >= extent {
+        return (0..cells, 0..0);
+    }
+
+    let cell_of = |position: f32| ((position / cell_size) as usize).min(cells - 1);
+    let start = min.rem_euclid(extent);
+    let end = start + (max - min);
+    if end < extent {
+        (cell_of(start)..cell_of(end) + 1, 0..0)
+    } else {
+        (cell_of(start)..cells, 0..cell_of(end - extent) + 1)
+    }
+}
+
+impl Grid {
+    /// Constructs an empty grid over a world of `width` by `height`, divided into square cells of
+    /// `cell_size`.
+    ///
+    /// For the best performance, `cell_size` should be about the diameter of the largest circle.
+    pub fn new(width: f32, height: f32, cell_size: f32) -> Self {
+        assert!(
+            width > 0.0 && height > 0.0 && cell_size > 0.0,
+            "the world and its cells must have a positive size"
+        );
+
+        let columns = (width / cell_size).ceil() as usize;
+        let rows = (height / cell_size).ceil() as usize;
+        Self {
+            width,
+            height,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        }
+    }
+
+    /// Removes all circles, keeping the allocated cells.
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+    }
+
+    /// Returns the indices of all cells that the circle at `center` with `radius` overlaps.
+    fn covered_cells(&self, center: [f32; 2], radius: f32) -> impl Iterator<Item = usize> {
+        let (columns, wrapped_columns) = covered_cells(
+            center[0] - radius,
+            center[0] + radius,
+            self.width,
+            self.columns,
+            self.cell_size,
+        );
+        let (rows, wrapped_rows) = covered_cells(
+            center[1] - radius,
+            center[1] + radius,
+            self.height,
+            self.rows,
+            self.cell_size,
+        );
+        let stride = self.columns;
+        rows.chain(wrapped_rows).flat_map(move |row| {
+            columns
+                .clone()
+                .chain(wrapped_columns.clone())
+                .map(move |column| row * stride + column)
+        })
+    }
+
+    /// Adds a circle, identified by `id`, to all cells it overlaps.
+    pub fn insert(&mut self, id: usize, center: [f32; 2], radius: f32) {
+        for index in self.covered_cells(center, radius) {
+            self.cells[index].push(id);
+        }
+    }
+
+    /// Replaces the contents of `candidates` with the sorted, unique ids of all circles that share
+    /// a cell with the circle at `center` with `radius`.
+    ///
+    /// Every circle that overlaps the queried circle is a candidate, but not every candidate
+    /// necessarily overlaps it.
+    pub fn query(&self, center: [f32; 2], radius: f32, candidates: &mut Vec<usize>) {
+        candidates.clear();
+        for index in self.covered_cells(center, radius) {
+            candidates.extend_from_slice(&self.cells[index]);
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+    }
+}
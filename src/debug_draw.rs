@@ -0,0 +1,128 @@
+//! Collects debug shapes, such as colliders and velocity vectors, for a host to render.
+
+/// What a debug shape visualises, so hosts can pick a color per kind of shape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Collider,
+    Velocity,
+    Origin,
+}
+
+/// An outlined shape, in game coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        center: [f32; 2],
+        radius: f32,
+    },
+    Rect {
+        position: [f32; 2],
+        width: f32,
+        height: f32,
+    },
+    Line {
+        from: [f32; 2],
+        to: [f32; 2],
+    },
+}
+
+/// The length of an arrow head, relative to the length of the arrow.
+const ARROW_HEAD_RATIO: f32 = 0.25;
+/// The angle between the shaft of an arrow and each side of its head, in radians.
+const ARROW_HEAD_ANGLE: f32 = 0.5;
+
+/// Returns the per-frame displacement of an object moving at `speed` in the direction of
+/// `angle`, in degrees, matching `move_object` in `spaceship.mun`.
+pub fn heading(angle: f32, speed: f32) -> [f32; 2] {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    [speed * sin, -speed * cos]
+}
+
+/// A toggleable list of debug shapes that is rebuilt every frame.
+#[derive(Default)]
+pub struct DebugDraw {
+    enabled: bool,
+    shapes: Vec<(Layer, Shape)>,
+}
+
+impl DebugDraw {
+    /// Whether debug shapes should be collected and drawn.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables debug drawing if it was disabled, and vice versa.
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Removes all shapes, to start collecting the next frame.
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// Returns the shapes collected since the last call to [`DebugDraw::clear`].
+    pub fn shapes(&self) -> &[(Layer, Shape)] {
+        &self.shapes
+    }
+
+    /// Adds the outline of a circle.
+    pub fn circle(&mut self, layer: Layer, center: [f32; 2], radius: f32) {
+        self.shapes.push((layer, Shape::Circle { center, radius }));
+    }
+
+    /// Adds the outline of an axis-aligned rectangle, with `position` as its top-left corner.
+    pub fn rect(&mut self, layer: Layer, position: [f32; 2], width: f32, height: f32) {
+        self.shapes.push((
+            layer,
+            Shape::Rect {
+                position,
+                width,
+                height,
+            },
+        ));
+    }
+
+    /// Adds a line segment.
+    pub fn line(&mut self, layer: Layer, from: [f32; 2], to: [f32; 2]) {
+        self.shapes.push((layer, Shape::Line { from, to }));
+    }
+
+    /// Adds an arrow pointing from `from` to `to`.
+    pub fn arrow(&mut self, layer: Layer, from: [f32; 2], to: [f32; 2]) {
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        self.line(layer, from, to);
+
+        let back = (-dx / length, -dy / length);
+        let head_length = length * ARROW_HEAD_RATIO;
+        for angle in [ARROW_HEAD_ANGLE, -ARROW_HEAD_ANGLE].iter() {
+            let (sin, cos) = angle.sin_cos();
+            let side = (back.0 * cos - back.1 * sin, back.0 * sin + back.1 * cos);
+            self.line(
+                layer,
+                to,
+                [to[0] + side.0 * head_length, to[1] + side.1 * head_length],
+            );
+        }
+    }
+
+    /// Adds a small cross centered on `center`, e.g. to mark the origin of a sprite.
+    pub fn cross(&mut self, layer: Layer, center: [f32; 2], size: f32) {
+        let half = size / 2.0;
+        self.line(
+            layer,
+            [center[0] - half, center[1]],
+            [center[0] + half, center[1]],
+        );
+        self.line(
+            layer,
+            [center[0], center[1] - half],
+            [center[0], center[1] + half],
+        );
+    }
+}
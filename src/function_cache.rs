@@ -0,0 +1,231 @@
+//! Caches the Mun functions that a host invokes every frame.
+//!
+//! `invoke_fn!` looks up a function by name and validates its signature for every call. A
+//! [`FunctionCache`] only does so the first time a function is called; afterwards the function is
+//! identified by a [`FunctionId`] and only the types of the arguments are compared, which does
+//! not involve any string lookups.
+//!
+//! Hot reloading replaces all functions, so the host has to call [`FunctionCache::invalidate`]
+//! whenever `Runtime::update` reports that the assemblies were reloaded.
+
+use crate::CallScope;
+use mun_runtime::{ArgumentReflection, Marshal, ReturnTypeReflection, Runtime, StructRef};
+use std::{ffi::c_void, mem};
+
+/// Identifies a function registered with a [`FunctionCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FunctionId(usize);
+
+/// The type of a value, as far as calling a function is concerned.
+///
+/// For return values, structs are only checked to be structs, like `invoke_fn!` does.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReturnType {
+    Empty,
+    Primitive([u8; 16]),
+    Struct,
+}
+
+struct Resolved {
+    fn_ptr: *const c_void,
+    arg_types: Vec<[u8; 16]>,
+    return_type: ReturnType,
+}
+
+struct CachedFunction {
+    name: &'static str,
+    resolved: Option<Resolved>,
+}
+
+/// Returns the type that `T` represents as a return value.
+fn return_type_of<T: ReturnTypeReflection>() -> ReturnType {
+    let guid = T::type_guid().0;
+    if guid == <() as ReturnTypeReflection>::type_guid().0 {
+        ReturnType::Empty
+    } else if guid == <StructRef as ReturnTypeReflection>::type_guid().0 {
+        ReturnType::Struct
+    } else {
+        ReturnType::Primitive(guid)
+    }
+}
+
+/// A collection of Mun functions that are looked up by name only once.
+#[derive(Default)]
+pub struct FunctionCache {
+    functions: Vec<CachedFunction>,
+}
+
+impl FunctionCache {
+    /// Registers the function called `name`, which is resolved the first time it is called.
+    pub fn register(&mut self, name: &'static str) -> FunctionId {
+        if let Some(index) = self.functions.iter().position(|f| f.name == name) {
+            return FunctionId(index);
+        }
+
+        self.functions.push(CachedFunction {
+            name,
+            resolved: None,
+        });
+        FunctionId(self.functions.len() - 1)
+    }
+
+    /// Forgets all resolved functions, e.g. because the runtime reloaded its assemblies.
+    pub fn invalidate(&mut self) {
+        for function in self.functions.iter_mut() {
+            function.resolved = None;
+        }
+    }
+
+    /// Returns the name of the function identified by `id`.
+    pub fn name(&self, id: FunctionId) -> &'static str {
+        self.functions[id.0].name
+    }
+
+    /// Resolves the function identified by `id`, if it wasn't already, and checks that it
+    /// accepts arguments of `arg_types` and returns `Output`.
+    fn resolve<Output: ReturnTypeReflection>(
+        &mut self,
+        id: FunctionId,
+        runtime: &Runtime,
+        arg_types: &[[u8; 16]],
+    ) -> Result<*const c_void, String> {
+        let function = &mut self.functions[id.0];
+        if function.resolved.is_none() {
+            let definition = runtime
+                .get_function_definition(function.name)
+                .ok_or_else(|| format!("Failed to obtain function '{}'", function.name))?;
+            let signature = &definition.prototype.signature;
+            function.resolved = Some(Resolved {
+                fn_ptr: definition.fn_ptr,
+                arg_types: signature
+                    .arg_types()
+                    .iter()
+                    .map(|type_info| type_info.guid.0)
+                    .collect(),
+                return_type: match signature.return_type() {
+                    None => ReturnType::Empty,
+                    Some(type_info) if type_info.as_struct().is_some() => ReturnType::Struct,
+                    Some(type_info) => ReturnType::Primitive(type_info.guid.0),
+                },
+            });
+        }
+
+        let resolved = function.resolved.as_ref().unwrap();
+        if resolved.arg_types.len() != arg_types.len() {
+            return Err(format!(
+                "Invalid number of arguments for '{}'. Expected: {}. Found: {}.",
+                function.name,
+                resolved.arg_types.len(),
+                arg_types.len(),
+            ));
+        }
+        if let Some(idx) =
+            (0..arg_types.len()).find(|&idx| resolved.arg_types[idx] != arg_types[idx])
+        {
+            return Err(format!(
+                "Invalid argument type at index {} for '{}'.",
+                idx, function.name
+            ));
+        }
+        if resolved.return_type != return_type_of::<Output>() {
+            return Err(format!(
+                "Invalid return type for '{}'. Found: {}.",
+                function.name,
+                Output::type_name()
+            ));
+        }
+
+        Ok(resolved.fn_ptr)
+    }
+}
+
+macro_rules! call_fns {
+    ($($call:ident, $for_each:ident($($arg:ident: $T:ident),*);)+) => {
+        // Functions without arguments don't use the lifetime of the arguments.
+        #[allow(clippy::extra_unused_lifetimes)]
+        impl FunctionCache {
+            $(
+                /// Calls the function identified by `id` with the given arguments.
+                #[allow(clippy::too_many_arguments)]
+                pub fn $call<'i, 'o, 'r, $($T,)* Output>(
+                    &mut self,
+                    id: FunctionId,
+                    runtime: &'r Runtime,
+                    $($arg: $T,)*
+                ) -> Result<Output, String>
+                where
+                    $($T: ArgumentReflection + Marshal<'i>,)*
+                    Output: 'o + ReturnTypeReflection + Marshal<'o>,
+                    'r: 'o,
+                {
+                    let arg_types = [$($arg.type_guid(runtime).0),*];
+                    let fn_ptr = self.resolve::<Output>(id, runtime, &arg_types)?;
+
+                    let _scope = CallScope::enter(self.name(id));
+                    // Safety: the signature of the function was checked against the types of
+                    // the arguments and the return value.
+                    let function = unsafe {
+                        mem::transmute::<*const c_void, fn($($T::MunType),*) -> Output::MunType>(
+                            fn_ptr,
+                        )
+                    };
+                    Ok(Marshal::marshal_from(function($($arg.marshal_into()),*), runtime))
+                }
+
+                /// Calls the function identified by `id` once for every set of arguments in
+                /// `args`, resolving the function only once for the whole batch.
+                pub fn $for_each<'i, $($T,)* I>(
+                    &mut self,
+                    id: FunctionId,
+                    runtime: &Runtime,
+                    args: I,
+                ) -> Result<(), String>
+                where
+                    $($T: ArgumentReflection + Marshal<'i>,)*
+                    I: IntoIterator<Item = ($($T,)*)>,
+                {
+                    let mut args = args.into_iter().peekable();
+                    let fn_ptr = match args.peek() {
+                        Some(($($arg,)*)) => {
+                            let arg_types = [$($arg.type_guid(runtime).0),*];
+                            self.resolve::<()>(id, runtime, &arg_types)?
+                        }
+                        None => return Ok(()),
+                    };
+                    let expected_types = self.functions[id.0]
+                        .resolved
+                        .as_ref()
+                        .map(|resolved| resolved.arg_types.clone())
+                        .unwrap_or_default();
+
+                    let mut scope = CallScope::enter_batch(self.name(id));
+                    // Safety: the signature of the function was checked against the types of
+                    // the arguments, which are checked again for every call below.
+                    let function = unsafe {
+                        mem::transmute::<*const c_void, fn($($T::MunType),*)>(fn_ptr)
+                    };
+                    for ($($arg,)*) in args {
+                        let arg_types: &[[u8; 16]] = &[$($arg.type_guid(runtime).0),*];
+                        if arg_types != &expected_types[..] {
+                            return Err(format!(
+                                "Invalid argument types for '{}'.",
+                                self.name(id)
+                            ));
+                        }
+                        function($($arg.marshal_into()),*);
+                        scope.add_call();
+                    }
+                    Ok(())
+                }
+            )+
+        }
+    };
+}
+
+call_fns! {
+    call0, for_each0();
+    call1, for_each1(a: A);
+    call2, for_each2(a: A, b: B);
+    call3, for_each3(a: A, b: B, c: C);
+    call4, for_each4(a: A, b: B, c: C, d: D);
+}
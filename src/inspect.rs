@@ -0,0 +1,239 @@
+//! Reflection over Mun structs, using the type information stored in the runtime.
+
+use mun_runtime::StructRef;
+use std::{convert::TryFrom, fmt};
+
+/// The value of a single field of a Mun struct.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A nested struct, identified by its type name.
+    Struct(String),
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Isize(isize),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Usize(usize),
+    F32(f32),
+    F64(f64),
+    /// A field with a type that cannot be inspected, identified by its type name.
+    Unsupported(String),
+}
+
+impl Value {
+    /// Returns the value after adjusting it by `steps`, or `None` if the value cannot be adjusted.
+    ///
+    /// Booleans are flipped for every step, integers change by one per step and floats change by
+    /// `float_step` per step.
+    pub fn adjusted(&self, steps: i32, float_step: f64) -> Option<Value> {
+        let value = match *self {
+            Value::Bool(value) => Value::Bool(value ^ (steps % 2 != 0)),
+            Value::I8(value) => Value::I8(value.saturating_add(clamped(steps, i8::MIN, i8::MAX))),
+            Value::I16(value) => {
+                Value::I16(value.saturating_add(clamped(steps, i16::MIN, i16::MAX)))
+            }
+            Value::I32(value) => Value::I32(value.saturating_add(steps)),
+            Value::I64(value) => Value::I64(value.saturating_add(steps.into())),
+            Value::Isize(value) => {
+                Value::Isize(value.saturating_add(clamped(steps, isize::MIN, isize::MAX)))
+            }
+            Value::U8(value) => Value::U8(add_signed(value.into(), steps, u8::MAX.into()) as u8),
+            Value::U16(value) => {
+                Value::U16(add_signed(value.into(), steps, u16::MAX.into()) as u16)
+            }
+            Value::U32(value) => {
+                Value::U32(add_signed(value.into(), steps, u32::MAX.into()) as u32)
+            }
+            Value::U64(value) => Value::U64(add_signed(value, steps, u64::MAX)),
+            Value::Usize(value) => {
+                Value::Usize(add_signed(value as u64, steps, usize::MAX as u64) as usize)
+            }
+            Value::F32(value) => Value::F32(value + (f64::from(steps) * float_step) as f32),
+            Value::F64(value) => Value::F64(value + f64::from(steps) * float_step),
+            Value::Struct(_) | Value::Unsupported(_) => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Converts `steps` to a narrower integer type, saturating at its `min` and `max`.
+fn clamped<T: TryFrom<i32>>(steps: i32, min: T, max: T) -> T {
+    T::try_from(steps).unwrap_or(if steps < 0 { min } else { max })
+}
+
+/// Adds `steps` to `value`, saturating at zero and `max`.
+fn add_signed(value: u64, steps: i32, max: u64) -> u64 {
+    if steps < 0 {
+        value.saturating_sub(steps.unsigned_abs().into())
+    } else {
+        value.saturating_add(steps as u64).min(max)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Struct(type_name) => write!(f, "{} {{ .. }}", type_name),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::I8(value) => write!(f, "{}", value),
+            Value::I16(value) => write!(f, "{}", value),
+            Value::I32(value) => write!(f, "{}", value),
+            Value::I64(value) => write!(f, "{}", value),
+            Value::Isize(value) => write!(f, "{}", value),
+            Value::U8(value) => write!(f, "{}", value),
+            Value::U16(value) => write!(f, "{}", value),
+            Value::U32(value) => write!(f, "{}", value),
+            Value::U64(value) => write!(f, "{}", value),
+            Value::Usize(value) => write!(f, "{}", value),
+            Value::F32(value) => write!(f, "{:.3}", value),
+            Value::F64(value) => write!(f, "{:.3}", value),
+            Value::Unsupported(type_name) => write!(f, "<{}>", type_name),
+        }
+    }
+}
+
+/// A field of a Mun struct, found by recursively walking its fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// Dot-separated path from the root struct, e.g. `ball.vel.x`.
+    pub path: String,
+    /// The number of structs between the root struct and this field.
+    pub depth: usize,
+    pub value: Value,
+}
+
+impl Field {
+    /// Returns the name of the field, without the path of its parents.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('.').next().unwrap_or(&self.path)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:indent$}{}: ",
+            "",
+            self.name(),
+            indent = self.depth * 2
+        )?;
+        match &self.value {
+            Value::Struct(type_name) => write!(f, "{}", type_name),
+            value => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Reads the field called `name` from `object`, dispatching on the name of its type.
+fn read_field(object: &StructRef, name: &str, type_name: &str) -> Value {
+    fn read<'s, T>(object: &StructRef<'s>, name: &str) -> T
+    where
+        T: 's + mun_runtime::ReturnTypeReflection + mun_runtime::Marshal<'s>,
+    {
+        object
+            .get(name)
+            .expect("field type was taken from the struct's type info")
+    }
+
+    match type_name {
+        "core::bool" => Value::Bool(read(object, name)),
+        "core::i8" => Value::I8(read(object, name)),
+        "core::i16" => Value::I16(read(object, name)),
+        "core::i32" => Value::I32(read(object, name)),
+        "core::i64" => Value::I64(read(object, name)),
+        "core::isize" => Value::Isize(read(object, name)),
+        "core::u8" => Value::U8(read(object, name)),
+        "core::u16" => Value::U16(read(object, name)),
+        "core::u32" => Value::U32(read(object, name)),
+        "core::u64" => Value::U64(read(object, name)),
+        "core::usize" => Value::Usize(read(object, name)),
+        "core::f32" => Value::F32(read(object, name)),
+        "core::f64" => Value::F64(read(object, name)),
+        type_name => Value::Unsupported(type_name.to_string()),
+    }
+}
+
+fn walk(object: &StructRef, prefix: &str, depth: usize, fields: &mut Vec<Field>) {
+    let type_info = object.type_info();
+    let struct_info = type_info
+        .as_struct()
+        .expect("a `StructRef` always refers to a struct");
+
+    for (name, field_type) in struct_info.field_names().zip(struct_info.field_types()) {
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+
+        if field_type.as_struct().is_some() {
+            let child: StructRef = object
+                .get(name)
+                .expect("field type was taken from the struct's type info");
+            fields.push(Field {
+                path: path.clone(),
+                depth,
+                value: Value::Struct(field_type.name().to_string()),
+            });
+            walk(&child, &path, depth + 1, fields);
+        } else {
+            fields.push(Field {
+                value: read_field(object, name, field_type.name()),
+                path,
+                depth,
+            });
+        }
+    }
+}
+
+/// Recursively collects all fields of `object`, in declaration order.
+///
+/// Fields that are structs themselves are listed before their own fields.
+pub fn fields(object: &StructRef) -> Vec<Field> {
+    let mut fields = Vec::new();
+    walk(object, "", 0, &mut fields);
+    fields
+}
+
+/// Writes a primitive `value` into the field called `name` of `object`.
+fn write_field(object: &mut StructRef, name: &str, value: Value) -> Result<(), String> {
+    match value {
+        Value::Bool(value) => object.set(name, value),
+        Value::I8(value) => object.set(name, value),
+        Value::I16(value) => object.set(name, value),
+        Value::I32(value) => object.set(name, value),
+        Value::I64(value) => object.set(name, value),
+        Value::Isize(value) => object.set(name, value),
+        Value::U8(value) => object.set(name, value),
+        Value::U16(value) => object.set(name, value),
+        Value::U32(value) => object.set(name, value),
+        Value::U64(value) => object.set(name, value),
+        Value::Usize(value) => object.set(name, value),
+        Value::F32(value) => object.set(name, value),
+        Value::F64(value) => object.set(name, value),
+        Value::Struct(type_name) | Value::Unsupported(type_name) => Err(format!(
+            "Cannot assign a value of type `{}` to `{}`.",
+            type_name, name
+        )),
+    }
+}
+
+/// Sets the primitive field at the dot-separated `path`, e.g. `ball.vel.x`, to `value`.
+pub fn set(object: &mut StructRef, path: &str, value: Value) -> Result<(), String> {
+    match path.split_once('.') {
+        None => write_field(object, path, value),
+        Some((name, rest)) => {
+            let mut child: StructRef = object.get(name)?;
+            set(&mut child, rest, value)?;
+            // Value structs are copied when they are read, so the modified copy needs to be
+            // written back. For GC structs this assigns the same reference again.
+            object.set(name, child)
+        }
+    }
+}
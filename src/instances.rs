@@ -0,0 +1,155 @@
+//! Several independent games that run the same Mun code, e.g. for split-screen, or for batches of
+//! headless games that train an AI or check that changes play out the same.
+//!
+//! All [`Instance`]s share one runtime, and so one munlib that is hot reloaded for all of them at
+//! once. Each has its own rooted state, random number generator and input. The externs of this
+//! crate collect their output per thread, so [`Instances::step`] runs the games one after another,
+//! and gives each the draw commands, sounds and effects that it requested.
+
+use crate::{
+    audio::{self, AudioRequest},
+    particles::{self, Effect},
+    random,
+    render_list::{self, DrawCommand},
+    snapshot::Snapshot,
+};
+use mun_runtime::{RootedStruct, Runtime, StructRef};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{cell::RefCell, mem, rc::Rc};
+
+/// One of the games of [`Instances`].
+pub struct Instance {
+    state: RootedStruct,
+    rng: StdRng,
+    /// Fields of the state that are set before every step, by their dot-separated path, e.g.
+    /// `paddle_left.move_up`.
+    pub input: Snapshot,
+    draw_commands: Vec<DrawCommand>,
+    audio_requests: Vec<AudioRequest>,
+    effects: Vec<Effect>,
+}
+
+impl Instance {
+    /// Returns the state of the game.
+    pub fn state(&self) -> StructRef<'_> {
+        self.state.by_ref().clone()
+    }
+
+    /// Returns the commands that were drawn during the last step.
+    pub fn draw_commands(&self) -> &[DrawCommand] {
+        &self.draw_commands
+    }
+
+    /// Returns the sounds that were requested during the last step.
+    pub fn audio_requests(&self) -> &[AudioRequest] {
+        &self.audio_requests
+    }
+
+    /// Returns the particle effects that were requested during the last step.
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+}
+
+/// Calls `f` while `rng` is the generator that Mun code and [`random::with_rng`] use.
+fn with_generator<T>(rng: &mut StdRng, f: impl FnOnce() -> T) -> T {
+    random::with_rng(|thread_rng| mem::swap(thread_rng, rng));
+    let result = f();
+    random::with_rng(|thread_rng| mem::swap(thread_rng, rng));
+    result
+}
+
+/// Games that run the same Mun code, each with their own state.
+pub struct Instances {
+    runtime: Rc<RefCell<Runtime>>,
+    instances: Vec<Instance>,
+}
+
+impl Instances {
+    /// Creates `count` games whose state is returned by the Mun function `new_state`. The
+    /// generator of each game is seeded with `seed` plus its index.
+    pub fn new(
+        runtime: Rc<RefCell<Runtime>>,
+        count: usize,
+        seed: u64,
+        new_state: &'static str,
+    ) -> Result<Self, String> {
+        let instances = (0..count)
+            .map(|index| {
+                let mut rng = StdRng::seed_from_u64(seed + index as u64);
+                let state = with_generator(&mut rng, || {
+                    let runtime_ref = runtime.borrow();
+                    let state: Result<StructRef, _> = invoke!(&runtime_ref, new_state);
+                    state
+                        .map(|state| state.root(runtime.clone()))
+                        .map_err(|e| format!("Failed to call `{}`: {:?}", new_state, e))
+                })?;
+
+                Ok(Instance {
+                    state,
+                    rng,
+                    input: Snapshot::default(),
+                    draw_commands: Vec::new(),
+                    audio_requests: Vec::new(),
+                    effects: Vec::new(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { runtime, instances })
+    }
+
+    /// Returns the runtime that all games share.
+    pub fn runtime(&self) -> &Rc<RefCell<Runtime>> {
+        &self.runtime
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Instance> {
+        self.instances.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Instance> {
+        self.instances.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter()
+    }
+
+    /// Runs one step of every game: sets its input, and calls `step` with its index and state,
+    /// which invokes the Mun functions that update, and possibly draw, the game.
+    pub fn step(
+        &mut self,
+        mut step: impl FnMut(usize, &Runtime, StructRef) -> Result<(), String>,
+    ) -> Result<(), String> {
+        // Output that was requested outside of a step doesn't belong to any game
+        render_list::take();
+        audio::take();
+        particles::take_effects();
+
+        let runtime = self.runtime.borrow();
+        for (index, instance) in self.instances.iter_mut().enumerate() {
+            let mut state = instance.state.by_ref().clone();
+            instance.input.restore(&mut state)?;
+            with_generator(&mut instance.rng, || step(index, &runtime, state))?;
+
+            instance.draw_commands = render_list::take();
+            instance.audio_requests = audio::take();
+            instance.effects = particles::take_effects();
+        }
+        Ok(())
+    }
+
+    /// Reloads the munlib if it changed, for all games, and returns whether it did.
+    pub fn update(&mut self) -> bool {
+        self.runtime.borrow_mut().update()
+    }
+}
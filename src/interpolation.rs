@@ -0,0 +1,125 @@
+//! Drawing objects between the last two ticks of a game that ticks at a fixed rate, so they move
+//! smoothly at any frame rate.
+//!
+//! After every tick, the host [`record`](Interpolator::record)s the [`Pose`] of every object.
+//! When drawing, [`Interpolator::pose`] returns the pose `alpha` of the way from the previous to
+//! the current tick, which the host passes to the Mun functions that draw the object. Objects that
+//! jumped rather than moved, e.g. because they respawned, are drawn where they are. In a world that
+//! wraps around at its edges, an object that crosses an edge moves across it, rather than back
+//! across the whole world.
+
+use mun_runtime::StructRef;
+use std::{collections::HashMap, hash::Hash, mem};
+
+/// Where an object is, and the direction it faces in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub position: [f32; 2],
+    pub angle: f32,
+}
+
+/// The poses of objects at the last two ticks, by a key that identifies each object.
+pub struct Interpolator<K> {
+    previous: HashMap<K, Pose>,
+    current: HashMap<K, Pose>,
+    /// The width and height of a world that wraps around at its edges.
+    wrap: Option<[f32; 2]>,
+    /// Objects that move further than this in a tick jumped.
+    max_distance: f32,
+}
+
+impl<K: Eq + Hash> Interpolator<K> {
+    /// Creates an interpolator for objects that move at most `max_distance` in a tick.
+    pub fn new(max_distance: f32) -> Self {
+        Self {
+            previous: HashMap::new(),
+            current: HashMap::new(),
+            wrap: None,
+            max_distance,
+        }
+    }
+
+    /// Creates an interpolator for objects in a world of `width` by `height` that wraps around at
+    /// its edges, which move at most `max_distance` in a tick.
+    pub fn wrapping(width: f32, height: f32, max_distance: f32) -> Self {
+        Self {
+            wrap: Some([width, height]),
+            ..Self::new(max_distance)
+        }
+    }
+
+    /// Starts a new tick, after which the poses of all objects need to be recorded again.
+    pub fn begin_tick(&mut self) {
+        self.previous = mem::take(&mut self.current);
+    }
+
+    /// Records the pose of the object with `key` after the current tick.
+    pub fn record(&mut self, key: K, pose: Pose) {
+        self.current.insert(key, pose);
+    }
+
+    /// Forgets all poses, e.g. after objects were replaced by a reload.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// Returns the pose of the object with `key` at `alpha` between the previous tick, `0.0`, and
+    /// the current tick, `1.0`, or `None` if it wasn't recorded in the current tick.
+    pub fn pose(&self, key: &K, alpha: f32) -> Option<Pose> {
+        let current = *self.current.get(key)?;
+        let previous = match self.previous.get(key) {
+            Some(previous) => previous,
+            None => return Some(current),
+        };
+
+        let wrap = self
+            .wrap
+            .map_or([None, None], |size| [Some(size[0]), Some(size[1])]);
+        let delta = [
+            delta(previous.position[0], current.position[0], wrap[0]),
+            delta(previous.position[1], current.position[1], wrap[1]),
+        ];
+        if delta[0].hypot(delta[1]) > self.max_distance {
+            return Some(current);
+        }
+
+        let mut position = [0.0; 2];
+        for axis in 0..2 {
+            position[axis] = previous.position[axis] + delta[axis] * alpha;
+            if let Some(extent) = wrap[axis] {
+                position[axis] = position[axis].rem_euclid(extent);
+            }
+        }
+        Some(Pose {
+            position,
+            angle: lerp_angle(previous.angle, current.angle, alpha),
+        })
+    }
+}
+
+/// Returns how far `to` is from `from` along an axis that may wrap at `extent`, the short way.
+fn delta(from: f32, to: f32, extent: Option<f32>) -> f32 {
+    let delta = to - from;
+    match extent {
+        Some(extent) if delta > extent / 2.0 => delta - extent,
+        Some(extent) if delta < -extent / 2.0 => delta + extent,
+        _ => delta,
+    }
+}
+
+/// Returns the angle `alpha` of the way from `from` to `to`, in degrees, turning the short way.
+pub fn lerp_angle(from: f32, to: f32, alpha: f32) -> f32 {
+    let mut delta = (to - from).rem_euclid(360.0);
+    if delta > 180.0 {
+        delta -= 360.0;
+    }
+    from + delta * alpha
+}
+
+/// Returns a key for a GC struct, its address, which stays the same until the munlib is reloaded
+/// or the struct is collected.
+pub fn object_key(object: &StructRef) -> usize {
+    // Safety: the pointer is only compared, never dereferenced
+    unsafe { object.clone().into_raw().get_ptr() as usize }
+}
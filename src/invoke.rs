@@ -0,0 +1,64 @@
+use crate::profiler;
+use std::{cell::RefCell, time::Instant};
+
+thread_local! {
+    static CALL_STACK: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks a Mun function as being executed for as long as the scope is alive.
+///
+/// Extern functions called from Mun have no way to tell who called them, so the host records
+/// the name of every entry point it invokes. The time spent in the function is recorded by the
+/// [`profiler`] when the scope ends.
+pub struct CallScope {
+    function_name: &'static str,
+    calls: u64,
+    start: Instant,
+}
+
+impl CallScope {
+    /// Pushes `function_name` onto the call stack of the current thread, for a single call.
+    pub fn enter(function_name: &'static str) -> Self {
+        let mut scope = Self::enter_batch(function_name);
+        scope.add_call();
+        scope
+    }
+
+    /// Pushes `function_name` onto the call stack of the current thread, for a batch of calls
+    /// that are counted with [`CallScope::add_call`].
+    pub fn enter_batch(function_name: &'static str) -> Self {
+        CALL_STACK.with(|stack| stack.borrow_mut().push(function_name));
+        Self {
+            function_name,
+            calls: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Counts another call made within this scope.
+    pub fn add_call(&mut self) {
+        self.calls += 1;
+    }
+}
+
+impl Drop for CallScope {
+    fn drop(&mut self) {
+        profiler::record(self.function_name, self.calls, self.start.elapsed());
+        CALL_STACK.with(|stack| stack.borrow_mut().pop());
+    }
+}
+
+/// Returns the name of the innermost Mun function that the host invoked and is still executing.
+pub fn current_function() -> Option<&'static str> {
+    CALL_STACK.with(|stack| stack.borrow().last().copied())
+}
+
+/// Invokes a Mun function, like `mun_runtime::invoke_fn!`, while keeping track of the function
+/// that is being executed and how long it takes.
+#[macro_export]
+macro_rules! invoke {
+    ($runtime:expr, $function_name:expr $(, $arg:expr)* $(,)?) => {{
+        let _scope = $crate::CallScope::enter($function_name);
+        $crate::mun_runtime::invoke_fn!($runtime, $function_name $(, $arg)*)
+    }};
+}
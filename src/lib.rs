@@ -0,0 +1,34 @@
+//! Host-side functionality shared by the Mun example games.
+//!
+//! Everything in here is independent of the game framework used by an example, so it can be
+//! reused by both the `ggez`-based and the `tetra`-based hosts.
+
+#[doc(hidden)]
+pub use mun_runtime;
+
+#[macro_use]
+mod invoke;
+pub mod assets;
+pub mod audio;
+pub mod background;
+pub mod broad_phase;
+pub mod debug_draw;
+pub mod function_cache;
+pub mod inspect;
+pub mod instances;
+pub mod interpolation;
+pub mod lockstep;
+pub mod logging;
+pub mod net;
+pub mod overlay;
+pub mod particles;
+pub mod profiler;
+pub mod random;
+pub mod render_list;
+pub mod rollback;
+pub mod snapshot;
+pub mod spaceship;
+pub mod theme;
+pub mod wrap;
+
+pub use invoke::{current_function, CallScope};
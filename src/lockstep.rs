@@ -0,0 +1,214 @@
+//! Lockstep networking, where both peers run the same Mun code from the same seed, and only
+//! exchange their input for every tick.
+//!
+//! A tick only runs once the input of both peers for it is known, so both peers apply the same
+//! input to the same state. Along with its input, every peer sends a hash of its state before the
+//! tick. When the hashes differ, the peers exchange those states to find the first field in which
+//! they diverged, which is reported as a [`Desync`].
+//!
+//! Every frame, a game:
+//!
+//! 1. passes its input and state to [`Lockstep::set_input`],
+//! 2. passes the received messages to [`Lockstep::receive`], and sends [`Lockstep::outgoing`],
+//! 3. stops if [`Lockstep::desync`] reports one, and
+//! 4. runs a tick with the inputs returned by [`Lockstep::advance`], if both are known.
+
+use crate::{inspect::Value, net::Message, snapshot::Snapshot};
+use std::{collections::BTreeMap, fmt};
+
+/// Returns a hash of all fields of `snapshot`, which is the same on every machine.
+pub fn hash(snapshot: &Snapshot) -> u64 {
+    // FNV-1a, because the hashers of the standard library don't promise stable output
+    let mut bytes = Vec::new();
+    snapshot.encode(&mut bytes);
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The first field in which two states differ. A value is `None` if the field is missing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+}
+
+/// Returns the first field, in declaration order, in which `local` and `remote` differ.
+pub fn first_difference(local: &Snapshot, remote: &Snapshot) -> Option<Difference> {
+    let len = local.fields.len().max(remote.fields.len());
+    (0..len).find_map(|index| {
+        let local = local.fields.get(index);
+        let remote = remote.fields.get(index);
+        match (local, remote) {
+            (Some(local), Some(remote)) if local == remote => None,
+            (Some((path, local)), Some((remote_path, remote))) if path == remote_path => {
+                Some(Difference {
+                    path: path.clone(),
+                    local: Some(local.clone()),
+                    remote: Some(remote.clone()),
+                })
+            }
+            // The peers' states have different fields, e.g. because they run different Mun code
+            (Some((path, local)), _) => Some(Difference {
+                path: path.clone(),
+                local: Some(local.clone()),
+                remote: None,
+            }),
+            (None, Some((path, remote))) => Some(Difference {
+                path: path.clone(),
+                local: None,
+                remote: Some(remote.clone()),
+            }),
+            (None, None) => None,
+        }
+    })
+}
+
+/// The peers' states differed before `tick`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Desync {
+    pub tick: u64,
+    /// The first field that differed, or `None` if the states are equal but were hashed
+    /// differently.
+    pub difference: Option<Difference>,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn value(value: &Option<Value>) -> String {
+            value
+                .as_ref()
+                .map_or_else(|| "missing".to_string(), |value| format!("{:?}", value))
+        }
+
+        write!(f, "The peers diverged before tick {}", self.tick)?;
+        match &self.difference {
+            Some(difference) => write!(
+                f,
+                ": `{}` is {} here, but {} on the other peer",
+                difference.path,
+                value(&difference.local),
+                value(&difference.remote)
+            ),
+            None => write!(f, ", but their states are equal"),
+        }
+    }
+}
+
+/// The local state before a tick, kept until the other peer confirmed it.
+struct History {
+    hash: u64,
+    state: Snapshot,
+}
+
+/// The progress of one peer in a lockstep game.
+#[derive(Default)]
+pub struct Lockstep {
+    tick: u64,
+    local_input: Option<Snapshot>,
+    remote_inputs: BTreeMap<u64, Snapshot>,
+    remote_hashes: BTreeMap<u64, u64>,
+    history: BTreeMap<u64, History>,
+    outgoing: Vec<Message>,
+    desync: Option<Desync>,
+    reported: bool,
+}
+
+impl Lockstep {
+    /// Returns the tick that runs next.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Sets the local input for the next tick, and the state before it, unless they were already
+    /// set. The `state` is only captured when it is needed.
+    pub fn set_input(
+        &mut self,
+        input: impl FnOnce() -> Snapshot,
+        state: impl FnOnce() -> Snapshot,
+    ) {
+        if self.local_input.is_some() {
+            return;
+        }
+
+        let input = input();
+        let state = state();
+        let hash = hash(&state);
+        self.outgoing.push(Message::Tick {
+            tick: self.tick,
+            input: input.clone(),
+            hash,
+        });
+        self.local_input = Some(input);
+        self.history.insert(self.tick, History { hash, state });
+        self.compare(self.tick);
+    }
+
+    /// Handles a message from the other peer. Messages that aren't part of a lockstep game are
+    /// ignored.
+    pub fn receive(&mut self, message: Message) {
+        match message {
+            Message::Tick { tick, input, hash } => {
+                self.remote_inputs.insert(tick, input);
+                self.remote_hashes.insert(tick, hash);
+                self.compare(tick);
+            }
+            Message::Desync { tick, state } => {
+                if let Some(history) = self.history.get(&tick) {
+                    self.desync = Some(Desync {
+                        tick,
+                        difference: first_difference(&history.state, &state),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Compares the hashes of the peers' states before `tick`, once both are known.
+    fn compare(&mut self, tick: u64) {
+        let (local, remote) = match (self.history.get(&tick), self.remote_hashes.get(&tick)) {
+            (Some(local), Some(&remote)) => (local, remote),
+            _ => return,
+        };
+
+        if local.hash != remote {
+            if !self.reported {
+                self.reported = true;
+                self.outgoing.push(Message::Desync {
+                    tick,
+                    state: local.state.clone(),
+                });
+            }
+        } else if !self.reported {
+            // Both peers agree on this state, so older states aren't needed anymore. After a
+            // desync, the diverged state is kept until the other peer's state arrives.
+            self.history = self.history.split_off(&(tick + 1));
+            self.remote_hashes = self.remote_hashes.split_off(&(tick + 1));
+        }
+    }
+
+    /// Returns the messages to send to the other peer.
+    pub fn outgoing(&mut self) -> Vec<Message> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Returns where the peers diverged, once it is known.
+    pub fn desync(&self) -> Option<&Desync> {
+        self.desync.as_ref()
+    }
+
+    /// Returns the local and remote input for the next tick if both are known, and moves on to
+    /// the tick after it.
+    pub fn advance(&mut self) -> Option<(Snapshot, Snapshot)> {
+        if self.local_input.is_none() || !self.remote_inputs.contains_key(&self.tick) {
+            return None;
+        }
+
+        let local = self.local_input.take().unwrap();
+        let remote = self.remote_inputs.remove(&self.tick).unwrap();
+        self.tick += 1;
+        Some((local, remote))
+    }
+}
@@ -0,0 +1,79 @@
+//! Extern functions that allow Mun scripts to emit log records.
+//!
+//! Every extern is named `log_<level>_<type>`, e.g. `log_info_f32` or `log_warn_vec2`. Records
+//! are emitted through the [`log`] crate with the `mun` target and are prefixed with the name of
+//! the Mun function that the host invoked, e.g. `[update]`. The host doesn't see the calls between
+//! Mun functions, so this is not necessarily the function that called the extern.
+//!
+//! To use them from a script, declare the externs you need:
+//!
+//! ```mun
+//! extern fn log_info_f32(value: f32);
+//! extern fn log_debug_vec2(x: f32, y: f32);
+//! ```
+
+use log::Level;
+use mun_runtime::RuntimeBuilder;
+use std::fmt;
+
+fn log_value(level: Level, value: fmt::Arguments) {
+    let function_name = crate::current_function().unwrap_or("<unknown>");
+    log::log!(target: "mun", level, "[{}] {}", function_name, value);
+}
+
+macro_rules! log_fns {
+    ($($level:expr => $f32:ident, $i32:ident, $u32:ident, $u8:ident, $bool:ident, $vec2:ident;)+) => {
+        $(
+            /// Logs an `f32` at the corresponding level.
+            pub extern "C" fn $f32(value: f32) {
+                log_value($level, format_args!("{}", value));
+            }
+
+            /// Logs an `i32` at the corresponding level.
+            pub extern "C" fn $i32(value: i32) {
+                log_value($level, format_args!("{}", value));
+            }
+
+            /// Logs a `u32` at the corresponding level.
+            pub extern "C" fn $u32(value: u32) {
+                log_value($level, format_args!("{}", value));
+            }
+
+            /// Logs a `u8` at the corresponding level.
+            pub extern "C" fn $u8(value: u8) {
+                log_value($level, format_args!("{}", value));
+            }
+
+            /// Logs a `bool` at the corresponding level.
+            pub extern "C" fn $bool(value: bool) {
+                log_value($level, format_args!("{}", value));
+            }
+
+            /// Logs the components of a two-dimensional vector at the corresponding level.
+            pub extern "C" fn $vec2(x: f32, y: f32) {
+                log_value($level, format_args!("Vec2 {{ x: {}, y: {} }}", x, y));
+            }
+        )+
+
+        /// Adds all logging externs to the runtime `builder`.
+        pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+            builder
+                $(
+                    .insert_fn(stringify!($f32), $f32 as extern "C" fn(f32))
+                    .insert_fn(stringify!($i32), $i32 as extern "C" fn(i32))
+                    .insert_fn(stringify!($u32), $u32 as extern "C" fn(u32))
+                    .insert_fn(stringify!($u8), $u8 as extern "C" fn(u8))
+                    .insert_fn(stringify!($bool), $bool as extern "C" fn(bool))
+                    .insert_fn(stringify!($vec2), $vec2 as extern "C" fn(f32, f32))
+                )+
+        }
+    };
+}
+
+log_fns! {
+    Level::Trace => log_trace_f32, log_trace_i32, log_trace_u32, log_trace_u8, log_trace_bool, log_trace_vec2;
+    Level::Debug => log_debug_f32, log_debug_i32, log_debug_u32, log_debug_u8, log_debug_bool, log_debug_vec2;
+    Level::Info => log_info_f32, log_info_i32, log_info_u32, log_info_u8, log_info_bool, log_info_vec2;
+    Level::Warn => log_warn_f32, log_warn_i32, log_warn_u32, log_warn_u8, log_warn_bool, log_warn_vec2;
+    Level::Error => log_error_f32, log_error_i32, log_error_u32, log_error_u8, log_error_bool, log_error_vec2;
+}
@@ -0,0 +1,221 @@
+//! Playing a game from two instances over TCP, on one machine or a LAN.
+//!
+//! One instance hosts the game and runs its Mun code. The other connects to it, and sends the
+//! input of its player as [`Message::Input`]. After every update, the host sends the whole game
+//! state back as [`Message::State`], which the other instance draws.
+//!
+//! In a lockstep game, both instances run the Mun code instead, from the same seed. They only
+//! exchange their input for every tick as [`Message::Tick`], see [`crate::lockstep`].
+//!
+//! Messages are framed by their length, and Nagle's algorithm is disabled so that small messages
+//! are sent right away.
+
+use crate::snapshot::Snapshot;
+use std::{
+    convert::TryInto,
+    io::{self, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+/// The address that games host on and connect to when none is given.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+/// The largest message that is accepted, to not allocate whatever a broken peer asks for.
+const MAX_MESSAGE_LEN: usize = 1 << 20;
+
+/// A message between the two instances of a game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// The input of the player of the connecting instance, e.g. `move_up` and `move_down`.
+    Input(Snapshot),
+    /// The game state after an update of the host.
+    State(Snapshot),
+    /// The seed of a lockstep game, which the host sends right after the other instance connected.
+    Start { seed: u64 },
+    /// The input of a lockstep peer for `tick`, and a hash of its state before that tick.
+    Tick {
+        tick: u64,
+        input: Snapshot,
+        hash: u64,
+    },
+    /// The state of a lockstep peer before `tick`, which it sends when the hashes of the peers
+    /// differ.
+    Desync { tick: u64, state: Snapshot },
+}
+
+impl Message {
+    /// Returns the message as bytes, without framing.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Message::Input(snapshot) => {
+                bytes.push(0);
+                snapshot.encode(&mut bytes);
+            }
+            Message::State(snapshot) => {
+                bytes.push(1);
+                snapshot.encode(&mut bytes);
+            }
+            Message::Start { seed } => {
+                bytes.push(2);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            Message::Tick { tick, input, hash } => {
+                bytes.push(3);
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                input.encode(&mut bytes);
+                bytes.extend_from_slice(&hash.to_le_bytes());
+            }
+            Message::Desync { tick, state } => {
+                bytes.push(4);
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                state.encode(&mut bytes);
+            }
+        }
+        bytes
+    }
+
+    /// Reads a message that was written by [`Message::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let (&tag, mut rest) = bytes.split_first().ok_or("empty message")?;
+        let message = match tag {
+            0 => Message::Input(decode_snapshot(&mut rest)?),
+            1 => Message::State(decode_snapshot(&mut rest)?),
+            2 => Message::Start {
+                seed: decode_u64(&mut rest)?,
+            },
+            3 => Message::Tick {
+                tick: decode_u64(&mut rest)?,
+                input: decode_snapshot(&mut rest)?,
+                hash: decode_u64(&mut rest)?,
+            },
+            4 => Message::Desync {
+                tick: decode_u64(&mut rest)?,
+                state: decode_snapshot(&mut rest)?,
+            },
+            tag => return Err(format!("unknown message {}", tag)),
+        };
+        if !rest.is_empty() {
+            return Err(format!("{} unexpected bytes after message", rest.len()));
+        }
+        Ok(message)
+    }
+}
+
+fn decode_snapshot(bytes: &mut &[u8]) -> Result<Snapshot, String> {
+    let (snapshot, rest) = Snapshot::decode(bytes)?;
+    *bytes = rest;
+    Ok(snapshot)
+}
+
+fn decode_u64(bytes: &mut &[u8]) -> Result<u64, String> {
+    if bytes.len() < 8 {
+        return Err("unexpected end of message".to_string());
+    }
+    let (value, rest) = bytes.split_at(8);
+    *bytes = rest;
+    Ok(u64::from_le_bytes(value.try_into().unwrap()))
+}
+
+/// A connection to the other instance of a game.
+///
+/// Messages are received on a separate thread, so [`Connection::receive`] never blocks a frame.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, String>>,
+    /// Why the connection was closed, once it is.
+    closed: Option<String>,
+}
+
+impl Connection {
+    /// Waits for the other instance to connect to `listener`.
+    pub fn accept(listener: &TcpListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        Self::new(stream)
+    }
+
+    /// Connects to the instance that hosts the game at `address`.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || loop {
+            let message = read_message(&mut reader);
+            let failed = message.is_err();
+            if sender.send(message).is_err() || failed {
+                break;
+            }
+        });
+
+        Ok(Self {
+            stream,
+            incoming,
+            closed: None,
+        })
+    }
+
+    /// Sends `message` to the other instance.
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        let bytes = message.encode();
+        let mut frame = Vec::with_capacity(4 + bytes.len());
+        frame.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&bytes);
+        self.stream.write_all(&frame)
+    }
+
+    /// Returns all messages received since the last call, or an error if the connection was
+    /// closed after all of them were returned.
+    pub fn receive(&mut self) -> Result<Vec<Message>, String> {
+        let mut messages = Vec::new();
+        while self.closed.is_none() {
+            match self.incoming.try_recv() {
+                Ok(Ok(message)) => messages.push(message),
+                Ok(Err(e)) => self.closed = Some(e),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = Some("The connection was closed".to_string())
+                }
+            }
+        }
+
+        match &self.closed {
+            Some(e) if messages.is_empty() => Err(e.clone()),
+            _ => Ok(messages),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // The receiving thread holds a clone of the stream, which would keep it open
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Reads one length-framed message from `reader`, blocking until it has arrived.
+fn read_message(reader: &mut impl Read) -> Result<Message, String> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => "The connection was closed".to_string(),
+        _ => format!("Failed to receive a message: {}", e),
+    })?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(format!(
+            "Received a message of {} bytes, which is too long",
+            len
+        ));
+    }
+
+    let mut bytes = vec![0; len];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|e| format!("Failed to receive a message: {}", e))?;
+    Message::decode(&bytes).map_err(|e| format!("Received an invalid message: {}", e))
+}
@@ -0,0 +1,167 @@
+//! A toggleable debug overlay that lists, and allows tweaking, the live state of a Mun struct.
+//! It can also show how much time was spent in each Mun function during the last frame.
+
+use crate::{
+    inspect::{self, Field, Value},
+    profiler,
+};
+use mun_runtime::StructRef;
+
+/// The amount a float field changes per step.
+const FLOAT_STEP: f64 = 1.0;
+/// The amount a float field changes per step, when making fine adjustments.
+const FINE_FLOAT_STEP: f64 = 0.1;
+
+/// Keeps track of what the debug overlay should display.
+///
+/// The overlay does not render itself, it only produces lines of text that the host draws with
+/// whichever framework it uses.
+#[derive(Default)]
+pub struct DebugOverlay {
+    show_state: bool,
+    show_profile: bool,
+    counters: Vec<(&'static str, String)>,
+    selected: Option<String>,
+}
+
+/// Returns whether `field` can be modified through the overlay.
+fn is_editable(field: &Field) -> bool {
+    !matches!(field.value, Value::Struct(_) | Value::Unsupported(_))
+}
+
+impl DebugOverlay {
+    /// Whether the overlay should currently be drawn.
+    pub fn is_visible(&self) -> bool {
+        self.show_state || self.show_profile
+    }
+
+    /// Whether the state of the Mun struct is shown, and can thus be tweaked.
+    pub fn is_state_visible(&self) -> bool {
+        self.show_state
+    }
+
+    /// Shows the state of the Mun struct if it was hidden, and vice versa.
+    pub fn toggle(&mut self) {
+        self.show_state = !self.show_state;
+    }
+
+    /// Shows the Mun calls of the last frame if they were hidden, and vice versa.
+    pub fn toggle_profile(&mut self) {
+        self.show_profile = !self.show_profile;
+    }
+
+    /// Sets a host counter, e.g. the number of entities or frames per second, that is displayed
+    /// above the state of the Mun struct.
+    pub fn set_counter<T: ToString>(&mut self, name: &'static str, value: T) {
+        let value = value.to_string();
+        match self
+            .counters
+            .iter_mut()
+            .find(|(counter, _)| *counter == name)
+        {
+            Some((_, current)) => *current = value,
+            None => self.counters.push((name, value)),
+        }
+    }
+
+    /// Returns the lines of text to draw for the host counters, followed by the Mun calls of
+    /// the last frame and every field of `state` if they are shown.
+    pub fn lines(&self, state: &StructRef) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .counters
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        if self.show_profile {
+            lines.push("-- Mun calls --".to_string());
+            lines.extend(profiler::last_frame().iter().map(|(name, stats)| {
+                format!(
+                    "  {}: {}x {:.3}ms",
+                    name,
+                    stats.calls,
+                    stats.time.as_secs_f64() * 1000.0
+                )
+            }));
+        }
+
+        if !self.show_state {
+            return lines;
+        }
+
+        lines.push(format!("-- {} --", state.type_info().name()));
+        lines.extend(inspect::fields(state).iter().map(|field| {
+            let marker = if self.selected.as_deref() == Some(field.path.as_str()) {
+                '>'
+            } else {
+                ' '
+            };
+            format!("{} {}", marker, field)
+        }));
+        lines
+    }
+
+    /// Returns the path of the selected field, if any.
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+
+    /// Selects the next editable field of `state`, wrapping around at the end.
+    pub fn select_next(&mut self, state: &StructRef) {
+        self.select_offset(state, 1);
+    }
+
+    /// Selects the previous editable field of `state`, wrapping around at the start.
+    pub fn select_previous(&mut self, state: &StructRef) {
+        self.select_offset(state, -1);
+    }
+
+    fn select_offset(&mut self, state: &StructRef, offset: isize) {
+        let paths: Vec<String> = inspect::fields(state)
+            .into_iter()
+            .filter(is_editable)
+            .map(|field| field.path)
+            .collect();
+        if paths.is_empty() {
+            self.selected = None;
+            return;
+        }
+
+        let current = self
+            .selected
+            .as_ref()
+            .and_then(|selected| paths.iter().position(|path| path == selected));
+        let index = match current {
+            Some(index) => (index as isize + offset).rem_euclid(paths.len() as isize) as usize,
+            None if offset < 0 => paths.len() - 1,
+            None => 0,
+        };
+        self.selected = Some(paths[index].clone());
+    }
+
+    /// Adjusts the selected field of `state` by `steps`.
+    ///
+    /// Booleans are toggled, integers change by one per step and floats by `1.0` per step, or by
+    /// `0.1` if `fine` is set.
+    pub fn adjust_selected(
+        &self,
+        state: &mut StructRef,
+        steps: i32,
+        fine: bool,
+    ) -> Result<(), String> {
+        let selected = match &self.selected {
+            Some(selected) => selected,
+            None => return Ok(()),
+        };
+        let field = inspect::fields(state)
+            .into_iter()
+            .find(|field| &field.path == selected)
+            .ok_or_else(|| format!("Field `{}` no longer exists.", selected))?;
+
+        let float_step = if fine { FINE_FLOAT_STEP } else { FLOAT_STEP };
+        match field.value.adjusted(steps, float_step) {
+            Some(value) => inspect::set(state, &field.path, value),
+            None => Ok(()),
+        }
+    }
+}
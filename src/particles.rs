@@ -0,0 +1,160 @@
+//! Short-lived particles for visual effects, such as explosions, that Mun code can trigger.
+//!
+//! Mun code requests effects through the externs registered by [`register`]. Every frame, the host
+//! [`take_effects`], emits particles for them from an [`Emitter`] of its choice, and updates and
+//! draws its [`ParticleSystem`].
+//!
+//! To use them from a script, declare the externs you need:
+//!
+//! ```mun
+//! extern fn spawn_explosion(x: f32, y: f32, size: f32);
+//! extern fn emit_thrust(x: f32, y: f32, angle: f32);
+//! ```
+
+use crate::{debug_draw::heading, theme::Color};
+use mun_runtime::RuntimeBuilder;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cell::RefCell;
+
+/// An effect requested by Mun code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Effect {
+    /// An explosion of an object at `position` with a radius of `size`.
+    Explosion { position: [f32; 2], size: f32 },
+    /// Exhaust from an engine at `position`, that propels an object in the direction of `angle`,
+    /// in degrees.
+    Thrust { position: [f32; 2], angle: f32 },
+}
+
+thread_local! {
+    static EFFECTS: RefCell<Vec<Effect>> = const { RefCell::new(Vec::new()) };
+}
+
+fn push(effect: Effect) {
+    EFFECTS.with(|effects| effects.borrow_mut().push(effect));
+}
+
+/// Requests an explosion of an object at `x`, `y` with a radius of `size`.
+pub extern "C" fn spawn_explosion(x: f32, y: f32, size: f32) {
+    push(Effect::Explosion {
+        position: [x, y],
+        size,
+    });
+}
+
+/// Requests exhaust from an engine at `x`, `y`, that propels an object in the direction of
+/// `angle`, in degrees.
+pub extern "C" fn emit_thrust(x: f32, y: f32, angle: f32) {
+    push(Effect::Thrust {
+        position: [x, y],
+        angle,
+    });
+}
+
+/// Adds all particle externs to the runtime `builder`.
+pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+    builder
+        .insert_fn(
+            "spawn_explosion",
+            spawn_explosion as extern "C" fn(f32, f32, f32),
+        )
+        .insert_fn("emit_thrust", emit_thrust as extern "C" fn(f32, f32, f32))
+}
+
+/// Returns the effects requested since the last call.
+pub fn take_effects() -> Vec<Effect> {
+    EFFECTS.with(|effects| std::mem::take(&mut *effects.borrow_mut()))
+}
+
+/// Describes a burst of particles. Ranges are given as `[min, max]`, and durations in frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    /// The number of particles in the burst.
+    pub count: usize,
+    /// The direction the particles move in, in degrees.
+    pub direction: f32,
+    /// The largest angle, in degrees, by which a particle's direction deviates from `direction`.
+    pub spread: f32,
+    /// The distance a particle moves per frame.
+    pub speed: [f32; 2],
+    pub lifetime: [f32; 2],
+    pub size: f32,
+    /// The color of a particle when it is emitted, which fades to `end_color` over its lifetime.
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+/// A single particle, in game coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub age: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    start_color: Color,
+    end_color: Color,
+}
+
+impl Particle {
+    /// Returns the color of the particle at its current age.
+    pub fn color(&self) -> Color {
+        let t = (self.age / self.lifetime).min(1.0);
+        let mut color = self.start_color;
+        for (component, end) in color.iter_mut().zip(self.end_color.iter()) {
+            *component += (end - *component) * t;
+        }
+        color
+    }
+}
+
+/// The particles that are currently alive.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: StdRng,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl ParticleSystem {
+    /// Returns the particles that are alive.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Emits a burst of particles at `position`.
+    pub fn emit(&mut self, emitter: &Emitter, position: [f32; 2]) {
+        let rng = &mut self.rng;
+        self.particles.extend((0..emitter.count).map(|_| {
+            let direction = emitter.direction + rng.gen_range(-emitter.spread..=emitter.spread);
+            let speed = rng.gen_range(emitter.speed[0]..=emitter.speed[1]);
+            Particle {
+                position,
+                velocity: heading(direction, speed),
+                age: 0.0,
+                lifetime: rng.gen_range(emitter.lifetime[0]..=emitter.lifetime[1]),
+                size: emitter.size,
+                start_color: emitter.start_color,
+                end_color: emitter.end_color,
+            }
+        }));
+    }
+
+    /// Moves and ages all particles by a frame, and removes those that outlived their lifetime.
+    pub fn update(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.position[0] += particle.velocity[0];
+            particle.position[1] += particle.velocity[1];
+            particle.age += 1.0;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+}
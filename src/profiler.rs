@@ -0,0 +1,123 @@
+//! Measures how often, and for how long, the host invokes each Mun function.
+//!
+//! Every call made through [`invoke!`](crate::invoke) is recorded, on whichever thread it is made,
+//! e.g. on the thread of a [`BackgroundSimulation`](crate::background::BackgroundSimulation). The
+//! host marks the end of each frame with [`end_frame`], after which the frame's measurements are
+//! available through [`last_frame`] and are added to the totals summarised by [`report`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs, io,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// The number of calls to a Mun function, and the cumulative time spent in them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallStats {
+    pub calls: u64,
+    pub time: Duration,
+}
+
+impl CallStats {
+    fn add(&mut self, other: CallStats) {
+        self.calls += other.calls;
+        self.time += other.time;
+    }
+}
+
+#[derive(Default)]
+struct Profiler {
+    current_frame: BTreeMap<&'static str, CallStats>,
+    last_frame: Vec<(&'static str, CallStats)>,
+    total: BTreeMap<&'static str, CallStats>,
+    frames: u64,
+}
+
+static PROFILER: Mutex<Profiler> = Mutex::new(Profiler {
+    current_frame: BTreeMap::new(),
+    last_frame: Vec::new(),
+    total: BTreeMap::new(),
+    frames: 0,
+});
+
+fn profiler() -> MutexGuard<'static, Profiler> {
+    // A thread that panicked while recording leaves nothing half-written that matters
+    PROFILER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sorts `stats` by descending time, so the most expensive functions come first.
+fn sorted_by_time(stats: &BTreeMap<&'static str, CallStats>) -> Vec<(&'static str, CallStats)> {
+    let mut stats: Vec<_> = stats.iter().map(|(name, stats)| (*name, *stats)).collect();
+    stats.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.time));
+    stats
+}
+
+/// Records `calls` calls to `function_name` that took `elapsed` in total.
+pub fn record(function_name: &'static str, calls: u64, elapsed: Duration) {
+    profiler()
+        .current_frame
+        .entry(function_name)
+        .or_default()
+        .add(CallStats {
+            calls,
+            time: elapsed,
+        });
+}
+
+/// Finishes the current frame, making its measurements available through [`last_frame`].
+pub fn end_frame() {
+    let mut profiler = profiler();
+    let current_frame = std::mem::take(&mut profiler.current_frame);
+    for (name, stats) in current_frame.iter() {
+        profiler.total.entry(name).or_default().add(*stats);
+    }
+    profiler.last_frame = sorted_by_time(&current_frame);
+    profiler.frames += 1;
+}
+
+/// Returns the measurements of the last finished frame, most expensive function first.
+pub fn last_frame() -> Vec<(&'static str, CallStats)> {
+    profiler().last_frame.clone()
+}
+
+/// Discards all measurements.
+pub fn reset() {
+    *profiler() = Profiler::default();
+}
+
+/// Formats the measurements of all finished frames as a table, most expensive function first.
+pub fn report() -> String {
+    let profiler = profiler();
+    let frames = profiler.frames.max(1) as f64;
+
+    let mut report = format!("Mun calls over {} frames\n", profiler.frames);
+    writeln!(
+        report,
+        "{:<32} {:>10} {:>12} {:>12} {:>12} {:>10}",
+        "function", "calls", "calls/frame", "total ms", "ms/frame", "us/call"
+    )
+    .unwrap();
+    for (name, stats) in sorted_by_time(&profiler.total) {
+        let total_ms = stats.time.as_secs_f64() * 1000.0;
+        writeln!(
+            report,
+            "{:<32} {:>10} {:>12.1} {:>12.3} {:>12.4} {:>10.3}",
+            name,
+            stats.calls,
+            stats.calls as f64 / frames,
+            total_ms,
+            total_ms / frames,
+            total_ms * 1000.0 / stats.calls as f64,
+        )
+        .unwrap();
+    }
+    report
+}
+
+/// Writes the [`report`] to the file at `path`.
+pub fn write_report<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    fs::write(path, report())
+}
@@ -0,0 +1,36 @@
+//! Random numbers for Mun code and hosts, from a generator that can be seeded so that a game plays
+//! out the same way every time, e.g. on both peers of a lockstep game.
+//!
+//! To use them from a script, declare the externs you need:
+//!
+//! ```mun
+//! extern fn rand_f32() -> f32;
+//! ```
+
+use mun_runtime::RuntimeBuilder;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Restarts the generator of this thread from `seed`.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Calls `f` with the generator of this thread, for random numbers that the host needs.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Returns a random number between `0.0` and `1.0`.
+pub extern "C" fn rand_f32() -> f32 {
+    with_rng(|rng| rng.gen())
+}
+
+/// Adds all random number externs to the runtime `builder`.
+pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+    builder.insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
+}
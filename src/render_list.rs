@@ -0,0 +1,163 @@
+//! A list of draw commands that Mun code fills every frame, so what is drawn is hot reloaded along
+//! with the rest of the game.
+//!
+//! The host invokes Mun drawing functions, which call the externs registered by [`register`].
+//! Afterwards the host [`take`]s the commands and draws them in order. Mun doesn't have strings, so
+//! it draws text by the id of a text in the asset manifest, or draws a number.
+//!
+//! To use them from a script, declare the externs you need:
+//!
+//! ```mun
+//! extern fn draw_color(r: f32, g: f32, b: f32, a: f32);
+//! extern fn draw_sprite(id: u32, x: f32, y: f32, rotation: f32, scale_x: f32, scale_y: f32);
+//! extern fn draw_rect(x: f32, y: f32, width: f32, height: f32);
+//! extern fn draw_circle(x: f32, y: f32, radius: f32);
+//! extern fn draw_number(value: u32, x: f32, y: f32);
+//! extern fn draw_text(id: u32, x: f32, y: f32);
+//! ```
+
+use crate::theme::Color;
+use mun_runtime::RuntimeBuilder;
+use std::cell::RefCell;
+
+const WHITE: Color = [1.0, 1.0, 1.0, 1.0];
+
+/// Something to draw, in game coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawCommand {
+    /// The sprite with `id` in the asset manifest, with its origin at `position`, rotated by
+    /// `rotation` degrees.
+    Sprite {
+        id: u32,
+        position: [f32; 2],
+        rotation: f32,
+        scale: [f32; 2],
+        color: Color,
+    },
+    /// A filled rectangle, with `position` as its top-left corner.
+    Rect {
+        position: [f32; 2],
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    /// A filled circle.
+    Circle {
+        center: [f32; 2],
+        radius: f32,
+        color: Color,
+    },
+    /// A number, with `position` as its top-left corner.
+    Number {
+        value: u32,
+        position: [f32; 2],
+        color: Color,
+    },
+    /// The text with `id` in the asset manifest, with `position` as its top-left corner.
+    Text {
+        id: u32,
+        position: [f32; 2],
+        color: Color,
+    },
+}
+
+struct RenderList {
+    color: Color,
+    commands: Vec<DrawCommand>,
+}
+
+thread_local! {
+    static RENDER_LIST: RefCell<RenderList> = const {
+        RefCell::new(RenderList {
+            color: WHITE,
+            commands: Vec::new(),
+        })
+    };
+}
+
+fn push(command: impl FnOnce(Color) -> DrawCommand) {
+    RENDER_LIST.with(|list| {
+        let mut list = list.borrow_mut();
+        let command = command(list.color);
+        list.commands.push(command);
+    });
+}
+
+/// Sets the color of the commands that follow, until the list is taken.
+pub extern "C" fn draw_color(r: f32, g: f32, b: f32, a: f32) {
+    RENDER_LIST.with(|list| list.borrow_mut().color = [r, g, b, a]);
+}
+
+/// Draws the sprite with `id`, with its origin at `x`, `y`, rotated by `rotation` degrees.
+pub extern "C" fn draw_sprite(id: u32, x: f32, y: f32, rotation: f32, scale_x: f32, scale_y: f32) {
+    push(|color| DrawCommand::Sprite {
+        id,
+        position: [x, y],
+        rotation,
+        scale: [scale_x, scale_y],
+        color,
+    });
+}
+
+/// Draws a filled rectangle, with its top-left corner at `x`, `y`.
+pub extern "C" fn draw_rect(x: f32, y: f32, width: f32, height: f32) {
+    push(|color| DrawCommand::Rect {
+        position: [x, y],
+        width,
+        height,
+        color,
+    });
+}
+
+/// Draws a filled circle, centered on `x`, `y`.
+pub extern "C" fn draw_circle(x: f32, y: f32, radius: f32) {
+    push(|color| DrawCommand::Circle {
+        center: [x, y],
+        radius,
+        color,
+    });
+}
+
+/// Draws `value`, with its top-left corner at `x`, `y`.
+pub extern "C" fn draw_number(value: u32, x: f32, y: f32) {
+    push(|color| DrawCommand::Number {
+        value,
+        position: [x, y],
+        color,
+    });
+}
+
+/// Draws the text with `id`, with its top-left corner at `x`, `y`.
+pub extern "C" fn draw_text(id: u32, x: f32, y: f32) {
+    push(|color| DrawCommand::Text {
+        id,
+        position: [x, y],
+        color,
+    });
+}
+
+/// Adds all drawing externs to the runtime `builder`.
+pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+    builder
+        .insert_fn(
+            "draw_color",
+            draw_color as extern "C" fn(f32, f32, f32, f32),
+        )
+        .insert_fn(
+            "draw_sprite",
+            draw_sprite as extern "C" fn(u32, f32, f32, f32, f32, f32),
+        )
+        .insert_fn("draw_rect", draw_rect as extern "C" fn(f32, f32, f32, f32))
+        .insert_fn("draw_circle", draw_circle as extern "C" fn(f32, f32, f32))
+        .insert_fn("draw_number", draw_number as extern "C" fn(u32, f32, f32))
+        .insert_fn("draw_text", draw_text as extern "C" fn(u32, f32, f32))
+}
+
+/// Returns the commands drawn since the last call, and resets the color to white.
+pub fn take() -> Vec<DrawCommand> {
+    RENDER_LIST.with(|list| {
+        let mut list = list.borrow_mut();
+        list.color = WHITE;
+        std::mem::take(&mut list.commands)
+    })
+}
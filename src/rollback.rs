@@ -0,0 +1,344 @@
+//! Rolling a game back to an earlier tick, for rollback netcode and rewinding.
+//!
+//! [`SavedStruct`] copies the memory of a Mun struct and of the structs it refers to, which is
+//! cheap enough to save the whole state of a game every tick.
+//!
+//! [`Rollback`] runs a game for two players without waiting for the input of the other player.
+//! It predicts that input instead, and when the actual input differs, restores the state before
+//! the mispredicted tick and simulates the ticks since with the actual input. [`LatencyLink`]
+//! delays messages by a number of ticks, to run this headlessly with simulated latency.
+
+use mun_abi::{StructMemoryKind, TypeInfo};
+use mun_runtime::StructRef;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    mem, ptr,
+    rc::Rc,
+};
+
+/// A field of a struct that refers to a GC struct, possibly inside a value struct field.
+struct GcField {
+    /// The offset of the reference from the start of the outer struct.
+    offset: usize,
+    /// The indices of the fields to follow from the outer struct to the reference.
+    path: Vec<usize>,
+}
+
+/// Where the references to other GC structs are in the memory of a struct type.
+struct Layout {
+    size: usize,
+    gc_fields: Vec<GcField>,
+}
+
+impl Layout {
+    fn new(type_info: &TypeInfo) -> Self {
+        fn collect(type_info: &TypeInfo, offset: usize, path: &[usize], fields: &mut Vec<GcField>) {
+            let struct_info = type_info.as_struct().expect("only structs have a layout");
+            for (index, (field_type, &field_offset)) in struct_info
+                .field_types()
+                .iter()
+                .zip(struct_info.field_offsets())
+                .enumerate()
+            {
+                let field_struct = match field_type.as_struct() {
+                    Some(field_struct) => field_struct,
+                    None => continue,
+                };
+                let offset = offset + usize::from(field_offset);
+                let mut path = path.to_vec();
+                path.push(index);
+                if field_struct.memory_kind == StructMemoryKind::GC {
+                    fields.push(GcField { offset, path });
+                } else {
+                    collect(field_type, offset, &path, fields);
+                }
+            }
+        }
+
+        let mut gc_fields = Vec::new();
+        collect(type_info, 0, &[], &mut gc_fields);
+        gc_fields.sort_by_key(|field| field.offset);
+        Self {
+            size: type_info.size_in_bytes(),
+            gc_fields,
+        }
+    }
+
+    /// Returns the layout of the type of `object`, which is computed once per type.
+    fn of(object: &StructRef) -> Rc<Layout> {
+        thread_local! {
+            static LAYOUTS: RefCell<HashMap<[u8; 16], Rc<Layout>>> = RefCell::new(HashMap::new());
+        }
+
+        let type_info = object.type_info();
+        LAYOUTS.with(|layouts| {
+            layouts
+                .borrow_mut()
+                .entry(type_info.guid.0)
+                .or_insert_with(|| Rc::new(Layout::new(type_info)))
+                .clone()
+        })
+    }
+}
+
+/// Follows `path`, a list of field indices, from `object` to a GC struct.
+fn follow<'s>(object: &StructRef<'s>, path: &[usize]) -> StructRef<'s> {
+    path.iter().fold(object.clone(), |object, &index| {
+        let struct_info = object.type_info().as_struct().unwrap();
+        let name = struct_info.field_names().nth(index).unwrap();
+        object
+            .get(name)
+            .expect("the path was taken from the struct's type info")
+    })
+}
+
+/// A copy of the memory of a Mun struct, and of the GC structs it refers to.
+///
+/// Restoring writes the copied values into the structs that the struct refers to when it is
+/// restored, which may be different ones than when it was saved. Structs that refer to each other
+/// in a cycle can't be saved.
+#[derive(Clone, Debug)]
+pub struct SavedStruct {
+    type_guid: [u8; 16],
+    bytes: Vec<u8>,
+    children: Vec<SavedStruct>,
+}
+
+impl SavedStruct {
+    /// Copies `object` and all GC structs it refers to.
+    pub fn save(object: &StructRef) -> Self {
+        let layout = Layout::of(object);
+        // Safety: the struct's memory is `size` bytes long, and nothing writes to it while it is
+        // copied
+        let bytes = unsafe {
+            let source = object.clone().into_raw().get_ptr();
+            std::slice::from_raw_parts(source, layout.size).to_vec()
+        };
+
+        Self {
+            type_guid: object.type_info().guid.0,
+            bytes,
+            children: layout
+                .gc_fields
+                .iter()
+                .map(|field| SavedStruct::save(&follow(object, &field.path)))
+                .collect(),
+        }
+    }
+
+    /// Writes the copy back into `object`, which needs to have the same type as the saved struct.
+    pub fn restore(&self, object: &mut StructRef) -> Result<(), String> {
+        if object.type_info().guid.0 != self.type_guid {
+            return Err(format!(
+                "Cannot restore a `{}` from a struct of another type",
+                object.type_info().name()
+            ));
+        }
+
+        // Copy everything except the references to other structs, which keep referring to the
+        // structs that are restored next
+        let layout = Layout::of(object);
+        let destination = unsafe { object.clone().into_raw().get_ptr() as *mut u8 };
+        let mut start = 0;
+        let ends = layout
+            .gc_fields
+            .iter()
+            .map(|field| (field.offset, field.offset + mem::size_of::<usize>()))
+            .chain(std::iter::once((layout.size, layout.size)));
+        for (end, next_start) in ends {
+            // Safety: the ranges lie within the struct, whose type, and thus size, matches
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.bytes.as_ptr().add(start),
+                    destination.add(start),
+                    end - start,
+                );
+            }
+            start = next_start;
+        }
+
+        for (field, saved) in layout.gc_fields.iter().zip(self.children.iter()) {
+            saved.restore(&mut follow(object, &field.path))?;
+        }
+        Ok(())
+    }
+}
+
+/// A game that [`Rollback`] can run, for two players.
+pub trait Simulation {
+    /// A copy of the state of the game.
+    type State;
+    /// The input of one player for one tick.
+    type Input: Clone + PartialEq;
+
+    fn save(&self) -> Self::State;
+    fn restore(&mut self, state: &Self::State);
+    /// Runs one tick with the input of both players, by player index.
+    fn step(&mut self, inputs: &[Self::Input; 2]);
+}
+
+/// A tick that ran, and what is needed to run it again.
+struct Entry<S: Simulation> {
+    /// The state before the tick.
+    state: S::State,
+    inputs: [S::Input; 2],
+}
+
+/// The progress of one player in a rollback game.
+pub struct Rollback<S: Simulation> {
+    player: usize,
+    max_prediction: u64,
+    /// The next tick to run.
+    tick: u64,
+    /// The ticks since the oldest tick that may still be rolled back, oldest first.
+    history: VecDeque<Entry<S>>,
+    /// The actual input of the other player for the ticks from `tick` onwards.
+    remote_ahead: VecDeque<S::Input>,
+    /// The number of ticks for which the other player's input is known.
+    confirmed: u64,
+    /// The other player's latest input, which is used as the prediction of its next input.
+    last_remote: S::Input,
+    /// The oldest tick that ran with a wrong prediction.
+    mispredicted: Option<u64>,
+    resimulated: u64,
+}
+
+impl<S: Simulation> Rollback<S> {
+    /// Starts a game as `player`, 0 or 1. The other player's input is predicted to be `input`
+    /// until it is known, and for at most `max_prediction` ticks.
+    pub fn new(player: usize, input: S::Input, max_prediction: u64) -> Self {
+        assert!(player < 2, "there are only two players");
+        Self {
+            player,
+            max_prediction,
+            tick: 0,
+            history: VecDeque::new(),
+            remote_ahead: VecDeque::new(),
+            confirmed: 0,
+            last_remote: input,
+            mispredicted: None,
+            resimulated: 0,
+        }
+    }
+
+    /// Returns the next tick to run.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the number of ticks for which the other player's input is known.
+    pub fn confirmed(&self) -> u64 {
+        self.confirmed
+    }
+
+    /// Returns the number of ticks that ran again because of a wrong prediction.
+    pub fn resimulated(&self) -> u64 {
+        self.resimulated
+    }
+
+    /// Handles the other player's input for `tick`. Inputs need to arrive in order, and inputs of
+    /// ticks that are already known are ignored.
+    pub fn add_remote_input(&mut self, tick: u64, input: S::Input) {
+        if tick != self.confirmed {
+            return;
+        }
+
+        let first_tick = self.tick - self.history.len() as u64;
+        if tick < self.tick {
+            let inputs = &mut self.history[(tick - first_tick) as usize].inputs;
+            if inputs[1 - self.player] != input {
+                inputs[1 - self.player] = input.clone();
+                self.mispredicted.get_or_insert(tick);
+            }
+        } else {
+            self.remote_ahead.push_back(input.clone());
+        }
+        self.last_remote = input;
+        self.confirmed += 1;
+
+        // Ticks before both the first unknown and the first mispredicted input never roll back
+        let keep_from = self
+            .mispredicted
+            .unwrap_or(self.confirmed)
+            .min(self.confirmed);
+        let drop = keep_from.saturating_sub(first_tick) as usize;
+        self.history.drain(..drop.min(self.history.len()));
+    }
+
+    /// Runs the next tick with the local `input` and returns it, unless the other player's input
+    /// is unknown for too many ticks. Ticks since a wrong prediction run again first.
+    pub fn advance(&mut self, simulation: &mut S, input: S::Input) -> Option<u64> {
+        self.resimulate(simulation);
+        if self.tick >= self.confirmed + self.max_prediction {
+            return None;
+        }
+
+        let remote = self
+            .remote_ahead
+            .pop_front()
+            .unwrap_or_else(|| self.last_remote.clone());
+        let mut inputs = [input.clone(), input];
+        inputs[1 - self.player] = remote;
+
+        self.history.push_back(Entry {
+            state: simulation.save(),
+            inputs: inputs.clone(),
+        });
+        simulation.step(&inputs);
+        self.tick += 1;
+        Some(self.tick - 1)
+    }
+
+    /// Restores the state before the first mispredicted tick, and runs the ticks since again.
+    fn resimulate(&mut self, simulation: &mut S) {
+        let tick = match self.mispredicted.take() {
+            Some(tick) => tick,
+            None => return,
+        };
+
+        let first_tick = self.tick - self.history.len() as u64;
+        let start = (tick - first_tick) as usize;
+        simulation.restore(&self.history[start].state);
+        for index in start..self.history.len() {
+            if index > start {
+                self.history[index].state = simulation.save();
+            }
+            simulation.step(&self.history[index].inputs);
+            self.resimulated += 1;
+        }
+    }
+}
+
+/// A one-way connection that delivers messages a fixed number of ticks after they were sent.
+pub struct LatencyLink<T> {
+    latency: u64,
+    in_flight: VecDeque<(u64, T)>,
+}
+
+impl<T> LatencyLink<T> {
+    pub fn new(latency: u64) -> Self {
+        Self {
+            latency,
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    /// Sends `message` in tick `now`.
+    pub fn send(&mut self, now: u64, message: T) {
+        self.in_flight.push_back((now + self.latency, message));
+    }
+
+    /// Returns the messages that arrived by tick `now`, in the order they were sent.
+    pub fn receive(&mut self, now: u64) -> Vec<T> {
+        let arrived = self
+            .in_flight
+            .iter()
+            .take_while(|(arrival, _)| *arrival <= now)
+            .count();
+        self.in_flight
+            .drain(..arrived)
+            .map(|(_, message)| message)
+            .collect()
+    }
+}
@@ -0,0 +1,175 @@
+//! Copies of the primitive fields of a Mun struct, which can be written back into a struct of the
+//! same type, or sent elsewhere as bytes.
+
+use crate::inspect::{self, Value};
+use mun_runtime::StructRef;
+use std::convert::TryInto;
+
+/// The primitive fields of a Mun struct, by their dot-separated path, e.g. `ball.vel.x`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Snapshot {
+    /// Copies all primitive fields of `object`, including those of nested structs.
+    pub fn capture(object: &StructRef) -> Self {
+        Self {
+            fields: inspect::fields(object)
+                .into_iter()
+                .filter(|field| !matches!(field.value, Value::Struct(_) | Value::Unsupported(_)))
+                .map(|field| (field.path, field.value))
+                .collect(),
+        }
+    }
+
+    /// Writes all fields back into `object`, which needs to have the same fields.
+    pub fn restore(&self, object: &mut StructRef) -> Result<(), String> {
+        for (path, value) in self.fields.iter() {
+            inspect::set(object, path, value.clone())
+                .map_err(|e| format!("Failed to restore `{}`: {}", path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the field at `path`.
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == path)
+            .map(|(_, value)| value)
+    }
+
+    /// Appends the snapshot to `bytes`, in a format that [`Snapshot::decode`] reads.
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(self.fields.len() as u32).to_le_bytes());
+        for (path, value) in self.fields.iter() {
+            encode_str(path, bytes);
+            encode_value(value, bytes);
+        }
+    }
+
+    /// Reads a snapshot that was written by [`Snapshot::encode`] from the start of `bytes`, and
+    /// returns it with the bytes that follow it.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, &[u8]), String> {
+        let mut reader = Reader(bytes);
+        let count = u32::from_le_bytes(reader.array()?);
+        let mut fields = Vec::new();
+        for _ in 0..count {
+            let path = reader.str()?;
+            fields.push((path, reader.value()?));
+        }
+        Ok((Self { fields }, reader.0))
+    }
+}
+
+fn encode_str(value: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+fn encode_value(value: &Value, bytes: &mut Vec<u8>) {
+    match value {
+        Value::Struct(type_name) => {
+            bytes.push(0);
+            encode_str(type_name, bytes);
+        }
+        Value::Bool(value) => bytes.extend_from_slice(&[1, u8::from(*value)]),
+        Value::I8(value) => {
+            bytes.push(2);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::I16(value) => {
+            bytes.push(3);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::I32(value) => {
+            bytes.push(4);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::I64(value) => {
+            bytes.push(5);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::Isize(value) => {
+            bytes.push(6);
+            bytes.extend_from_slice(&(*value as i64).to_le_bytes());
+        }
+        Value::U8(value) => bytes.extend_from_slice(&[7, *value]),
+        Value::U16(value) => {
+            bytes.push(8);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::U32(value) => {
+            bytes.push(9);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::U64(value) => {
+            bytes.push(10);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::Usize(value) => {
+            bytes.push(11);
+            bytes.extend_from_slice(&(*value as u64).to_le_bytes());
+        }
+        Value::F32(value) => {
+            bytes.push(12);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::F64(value) => {
+            bytes.push(13);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::Unsupported(type_name) => {
+            bytes.push(14);
+            encode_str(type_name, bytes);
+        }
+    }
+}
+
+/// Reads values from the front of a byte slice.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.0.len() < N {
+            return Err("unexpected end of snapshot".to_string());
+        }
+        let (array, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(array.try_into().unwrap())
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        if self.0.len() < len {
+            return Err("unexpected end of snapshot".to_string());
+        }
+        let (string, rest) = self.0.split_at(len);
+        self.0 = rest;
+        String::from_utf8(string.to_vec()).map_err(|e| format!("invalid field name: {}", e))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        let [tag] = self.array()?;
+        let value = match tag {
+            0 => Value::Struct(self.str()?),
+            1 => Value::Bool(self.array::<1>()? != [0]),
+            2 => Value::I8(i8::from_le_bytes(self.array()?)),
+            3 => Value::I16(i16::from_le_bytes(self.array()?)),
+            4 => Value::I32(i32::from_le_bytes(self.array()?)),
+            5 => Value::I64(i64::from_le_bytes(self.array()?)),
+            6 => Value::Isize(i64::from_le_bytes(self.array()?) as isize),
+            7 => Value::U8(u8::from_le_bytes(self.array()?)),
+            8 => Value::U16(u16::from_le_bytes(self.array()?)),
+            9 => Value::U32(u32::from_le_bytes(self.array()?)),
+            10 => Value::U64(u64::from_le_bytes(self.array()?)),
+            11 => Value::Usize(u64::from_le_bytes(self.array()?) as usize),
+            12 => Value::F32(f32::from_le_bytes(self.array()?)),
+            13 => Value::F64(f64::from_le_bytes(self.array()?)),
+            14 => Value::Unsupported(self.str()?),
+            tag => return Err(format!("unknown value tag {}", tag)),
+        };
+        Ok(value)
+    }
+}
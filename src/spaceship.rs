@@ -0,0 +1,446 @@
+//! The host side of the spaceship game, without drawing it.
+//!
+//! `spaceship.mun` updates the spaceship, and single rockets and asteroids. The host keeps the
+//! rockets and asteroids in vectors: every [`Spaceship::tick`] spawns and removes them, finds
+//! which of them collide, and then updates the spaceship with the player's input. The `spaceship`
+//! example draws the game, and the tests play it headlessly, both with this same tick.
+
+use crate::{
+    broad_phase::Grid,
+    function_cache::{FunctionCache, FunctionId},
+    inspect::Value,
+    logging, random,
+    rollback::SavedStruct,
+    snapshot::Snapshot,
+};
+use mun_runtime::{RootedStruct, Runtime, RuntimeBuilder, StructRef};
+use rand::Rng;
+use std::{cell::RefCell, rc::Rc};
+
+/// The size of the cells of the broad phase grid, about the diameter of the largest asteroid.
+const BROAD_PHASE_CELL_SIZE: f32 = 32.0;
+
+extern "C" fn sin(number: f32) -> f32 {
+    number.sin()
+}
+
+extern "C" fn cos(number: f32) -> f32 {
+    number.cos()
+}
+
+extern "C" fn degrees_to_radians(degrees: f32) -> f32 {
+    degrees.to_radians()
+}
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+/// The width of the game area, which wraps around at its edges.
+pub extern "C" fn game_area_width() -> f32 {
+    128.0 * 5.0
+}
+
+/// The height of the game area, which wraps around at its edges.
+pub extern "C" fn game_area_height() -> f32 {
+    72.0 * 5.0
+}
+
+/// The fields of `PlayerInput` that each player controls when two play together, by player
+/// index: the first player steers the spaceship, and the second one shoots.
+pub const PLAYER_INPUTS: [&[&str]; 2] = [&["left", "right", "up"], &["shoot"]];
+
+/// Adds the externs that only the spaceship game uses to the runtime `builder`. The game also
+/// needs those of [`audio`](crate::audio), [`particles`](crate::particles),
+/// [`render_list`](crate::render_list) and [`logging`].
+pub fn register(builder: RuntimeBuilder) -> RuntimeBuilder {
+    builder
+        .insert_fn("sin", sin as extern "C" fn(number: f32) -> f32)
+        .insert_fn("cos", cos as extern "C" fn(number: f32) -> f32)
+        .insert_fn("dbg", logging::log_debug_f32 as extern "C" fn(number: f32))
+        .insert_fn(
+            "degrees_to_radians",
+            degrees_to_radians as extern "C" fn(degrees: f32) -> f32,
+        )
+        .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32)
+        .insert_fn("game_area_width", game_area_width as extern "C" fn() -> f32)
+        .insert_fn(
+            "game_area_height",
+            game_area_height as extern "C" fn() -> f32,
+        )
+}
+
+/// The Mun functions that a tick invokes.
+struct Functions {
+    cache: FunctionCache,
+    new_game_struct: FunctionId,
+    new_player_input: FunctionId,
+    max_rockets_amount: FunctionId,
+    initial_asteroids_amount: FunctionId,
+    new_vec2: FunctionId,
+    new_rocket: FunctionId,
+    new_asteroid: FunctionId,
+    update_rocket: FunctionId,
+    update_asteroid: FunctionId,
+    object_collide: FunctionId,
+    update: FunctionId,
+}
+
+impl Functions {
+    fn new() -> Self {
+        let mut cache = FunctionCache::default();
+        Self {
+            new_game_struct: cache.register("new_game_struct"),
+            new_player_input: cache.register("new_player_input"),
+            max_rockets_amount: cache.register("max_rockets_amount"),
+            initial_asteroids_amount: cache.register("initial_asteroids_amount"),
+            new_vec2: cache.register("new_vec2"),
+            new_rocket: cache.register("new_rocket"),
+            new_asteroid: cache.register("new_asteroid"),
+            update_rocket: cache.register("update_rocket"),
+            update_asteroid: cache.register("update_asteroid"),
+            object_collide: cache.register("object_collide"),
+            update: cache.register("update"),
+            cache,
+        }
+    }
+}
+
+/// A copy of the state of a [`Spaceship`] game.
+///
+/// Rockets and asteroids are copied by value, so a saved game doesn't keep any of them alive, and
+/// restoring it creates those that were destroyed since.
+#[derive(Clone, Debug)]
+pub struct SavedSpaceship {
+    game_struct: SavedStruct,
+    rockets: Vec<SavedStruct>,
+    asteroids: Vec<SavedStruct>,
+    score: u8,
+}
+
+/// A game of spaceship, with its rockets and asteroids.
+///
+/// The functions that a tick invokes are looked up only once, so the munlib needs to be reloaded
+/// with [`Spaceship::update`], which looks them up again afterwards.
+pub struct Spaceship {
+    runtime: Rc<RefCell<Runtime>>,
+    /// The spaceship, and when to spawn rockets and asteroids.
+    pub game_struct: RootedStruct,
+    pub rockets: Vec<RootedStruct>,
+    pub asteroids: Vec<RootedStruct>,
+    /// The number of asteroids that were hit since the spaceship last crashed.
+    pub score: u8,
+    functions: Functions,
+    broad_phase: Grid,
+    collision_candidates: Vec<usize>,
+}
+
+impl Spaceship {
+    /// Starts a game in `runtime`, which needs the externs of [`register`].
+    pub fn new(runtime: Rc<RefCell<Runtime>>) -> Result<Self, String> {
+        let mut functions = Functions::new();
+        let game_struct = {
+            let runtime_ref = runtime.borrow();
+            let game_struct: StructRef = functions
+                .cache
+                .call0(functions.new_game_struct, &runtime_ref)?;
+            game_struct.root(runtime.clone())
+        };
+
+        Ok(Self {
+            runtime,
+            game_struct,
+            rockets: Vec::new(),
+            asteroids: Vec::new(),
+            score: 0,
+            functions,
+            broad_phase: Grid::new(game_area_width(), game_area_height(), BROAD_PHASE_CELL_SIZE),
+            collision_candidates: Vec::new(),
+        })
+    }
+
+    /// Returns the runtime that the game runs in.
+    pub fn runtime(&self) -> &Rc<RefCell<Runtime>> {
+        &self.runtime
+    }
+
+    /// Reloads the munlib if it changed, and returns whether it did.
+    pub fn update(&mut self) -> bool {
+        let reloaded = self.runtime.borrow_mut().update();
+        if reloaded {
+            self.functions.cache.invalidate();
+        }
+        reloaded
+    }
+
+    /// Runs a tick with `input`, the fields of the player's `PlayerInput`, e.g. `shoot`.
+    pub fn tick(&mut self, input: &Snapshot) -> Result<(), String> {
+        let runtime = self.runtime.clone();
+        let runtime_ref = runtime.borrow();
+        let functions = &mut self.functions;
+
+        let player_input: StructRef = functions
+            .cache
+            .call0(functions.new_player_input, &runtime_ref)?;
+        let player_input = player_input.root(runtime.clone());
+        let mut player_input = player_input.by_ref().clone();
+        input.restore(&mut player_input)?;
+
+        let mut game_struct = self.game_struct.by_ref().clone();
+        if game_struct.get::<bool>("spawn_new_rocket")? {
+            game_struct.set("spawn_new_rocket", false)?;
+
+            let max_rockets: usize = functions
+                .cache
+                .call0(functions.max_rockets_amount, &runtime_ref)?;
+            if self.rockets.len() < max_rockets {
+                let object = spaceship_object(&game_struct)?;
+                let rocket: StructRef = functions.cache.call2(
+                    functions.new_rocket,
+                    &runtime_ref,
+                    object.get::<StructRef>("position")?,
+                    object.get::<f32>("angle")?,
+                )?;
+                self.rockets.push(rocket.root(runtime.clone()));
+            }
+        }
+
+        if game_struct.get::<bool>("spawn_new_asteroids")? {
+            game_struct.set("spawn_new_asteroids", false)?;
+
+            let amount: i32 = functions
+                .cache
+                .call0(functions.initial_asteroids_amount, &runtime_ref)?;
+            self.asteroids.clear();
+            for _ in 0..amount {
+                let (x, y) = random::with_rng(|rng| {
+                    if rng.gen_range(0..1) == 0 {
+                        (0.0, rng.gen_range(0.0..game_area_height()))
+                    } else {
+                        (0.0, rng.gen_range(game_area_width()..0.0))
+                    }
+                });
+                let position: StructRef =
+                    functions
+                        .cache
+                        .call2(functions.new_vec2, &runtime_ref, x, y)?;
+                let asteroid: StructRef = functions.cache.call3(
+                    functions.new_asteroid,
+                    &runtime_ref,
+                    position,
+                    random_angle(),
+                    3_u8,
+                )?;
+                self.asteroids.push(asteroid.root(runtime.clone()));
+            }
+        }
+
+        functions.cache.for_each1(
+            functions.update_rocket,
+            &runtime_ref,
+            self.rockets.iter().map(|rocket| (rocket.by_ref().clone(),)),
+        )?;
+        remove_destroyed(&mut self.rockets)?;
+
+        functions.cache.for_each1(
+            functions.update_asteroid,
+            &runtime_ref,
+            self.asteroids
+                .iter()
+                .map(|asteroid| (asteroid.by_ref().clone(),)),
+        )?;
+
+        // Destroyed asteroids split in two, which go after the others
+        let mut splits = Vec::new();
+        for asteroid in self.asteroids.iter() {
+            let asteroid = asteroid.by_ref();
+            let size: u8 = asteroid.get("size")?;
+            if !asteroid.get::<bool>("need_to_destroy")? || size <= 1 {
+                continue;
+            }
+            let object = asteroid.get::<StructRef>("object")?;
+            for _ in 0..2 {
+                let split: StructRef = functions.cache.call3(
+                    functions.new_asteroid,
+                    &runtime_ref,
+                    object.get::<StructRef>("position")?,
+                    random_angle(),
+                    size - 1,
+                )?;
+                splits.push(split.root(runtime.clone()));
+            }
+        }
+        remove_destroyed(&mut self.asteroids)?;
+        self.asteroids.extend(splits);
+
+        // Only pairs that share a cell of the broad phase grid can collide
+        self.broad_phase.clear();
+        for (index, asteroid) in self.asteroids.iter().enumerate() {
+            let (center, radius) = collider(&asteroid.by_ref().get("object")?)?;
+            self.broad_phase.insert(index, center, radius);
+        }
+
+        for rocket in self.rockets.iter() {
+            let mut rocket = rocket.by_ref().clone();
+            let (center, radius) = collider(&rocket.get("object")?)?;
+            self.broad_phase
+                .query(center, radius, &mut self.collision_candidates);
+
+            for &index in self.collision_candidates.iter() {
+                let mut asteroid = self.asteroids[index].by_ref().clone();
+                let collide: bool = functions.cache.call2(
+                    functions.object_collide,
+                    &runtime_ref,
+                    rocket.get::<StructRef>("object")?,
+                    asteroid.get::<StructRef>("object")?,
+                )?;
+                if collide {
+                    self.score += 1;
+                    rocket.set("need_to_destroy", true)?;
+                    asteroid.set("need_to_destroy", true)?;
+                }
+            }
+        }
+
+        let (center, radius) = collider(&spaceship_object(&game_struct)?)?;
+        self.broad_phase
+            .query(center, radius, &mut self.collision_candidates);
+        for &index in self.collision_candidates.iter() {
+            let collide: bool = functions.cache.call2(
+                functions.object_collide,
+                &runtime_ref,
+                spaceship_object(&game_struct)?,
+                self.asteroids[index].by_ref().get::<StructRef>("object")?,
+            )?;
+            if collide {
+                game_struct.set("token", random::with_rng(|rng| rng.gen::<u8>()))?;
+                self.rockets.clear();
+                self.score = 0;
+            }
+        }
+
+        if self.asteroids.is_empty() {
+            game_struct.set("spawn_new_asteroids", true)?;
+        }
+
+        functions
+            .cache
+            .call2(functions.update, &runtime_ref, game_struct, player_input)
+    }
+
+    /// Copies the state of the game.
+    pub fn save(&self) -> SavedSpaceship {
+        let save_all = |entities: &[RootedStruct]| {
+            entities
+                .iter()
+                .map(|entity| SavedStruct::save(&entity.by_ref()))
+                .collect()
+        };
+        SavedSpaceship {
+            game_struct: SavedStruct::save(&self.game_struct.by_ref()),
+            rockets: save_all(&self.rockets),
+            asteroids: save_all(&self.asteroids),
+            score: self.score,
+        }
+    }
+
+    /// Restores the state that `saved` copied. The rockets and asteroids that the game has are
+    /// reused, and new ones are created if there are fewer than were saved.
+    pub fn restore(&mut self, saved: &SavedSpaceship) -> Result<(), String> {
+        let runtime = self.runtime.clone();
+        let runtime_ref = runtime.borrow();
+        let functions = &mut self.functions;
+
+        saved
+            .game_struct
+            .restore(&mut self.game_struct.by_ref().clone())?;
+
+        // Every field of the created entities is overwritten, so they start out anywhere
+        self.rockets.truncate(saved.rockets.len());
+        while self.rockets.len() < saved.rockets.len() {
+            let position: StructRef =
+                functions
+                    .cache
+                    .call2(functions.new_vec2, &runtime_ref, 0.0_f32, 0.0_f32)?;
+            let rocket: StructRef =
+                functions
+                    .cache
+                    .call2(functions.new_rocket, &runtime_ref, position, 0.0_f32)?;
+            self.rockets.push(rocket.root(runtime.clone()));
+        }
+        self.asteroids.truncate(saved.asteroids.len());
+        while self.asteroids.len() < saved.asteroids.len() {
+            let position: StructRef =
+                functions
+                    .cache
+                    .call2(functions.new_vec2, &runtime_ref, 0.0_f32, 0.0_f32)?;
+            let asteroid: StructRef = functions.cache.call3(
+                functions.new_asteroid,
+                &runtime_ref,
+                position,
+                0.0_f32,
+                1_u8,
+            )?;
+            self.asteroids.push(asteroid.root(runtime.clone()));
+        }
+
+        let entities = self
+            .rockets
+            .iter()
+            .zip(saved.rockets.iter())
+            .chain(self.asteroids.iter().zip(saved.asteroids.iter()));
+        for (entity, saved) in entities {
+            saved.restore(&mut entity.by_ref().clone())?;
+        }
+        self.score = saved.score;
+        Ok(())
+    }
+
+    /// Returns all fields of the game struct, and of every rocket and asteroid, e.g.
+    /// `rockets.0.life_timer`, along with the score.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::capture(&self.game_struct.by_ref());
+        for (name, entities) in [("rockets", &self.rockets), ("asteroids", &self.asteroids)] {
+            for (index, entity) in entities.iter().enumerate() {
+                let fields = Snapshot::capture(&entity.by_ref()).fields.into_iter();
+                snapshot.fields.extend(
+                    fields.map(|(path, value)| (format!("{}.{}.{}", name, index, path), value)),
+                );
+            }
+        }
+        snapshot
+            .fields
+            .push(("score".to_string(), Value::U8(self.score)));
+        snapshot
+    }
+}
+
+fn random_angle() -> f32 {
+    random::with_rng(|rng| rng.gen_range(0.0_f32..360.0_f32))
+}
+
+fn spaceship_object<'s>(game_struct: &StructRef<'s>) -> Result<StructRef<'s>, String> {
+    game_struct
+        .get::<StructRef>("spaceship")?
+        .get::<StructRef>("object")
+}
+
+/// Returns the center and radius of the circle that `object` collides with.
+fn collider(object: &StructRef) -> Result<([f32; 2], f32), String> {
+    let position = object.get::<StructRef>("position")?;
+    Ok((
+        [position.get("x")?, position.get("y")?],
+        object.get("radius")?,
+    ))
+}
+
+/// Removes the rockets or asteroids that need to be destroyed.
+fn remove_destroyed(entities: &mut Vec<RootedStruct>) -> Result<(), String> {
+    let destroyed = entities
+        .iter()
+        .map(|entity| entity.by_ref().get::<bool>("need_to_destroy"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut destroyed = destroyed.into_iter();
+    entities.retain(|_| !destroyed.next().unwrap());
+    Ok(())
+}
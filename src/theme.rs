@@ -0,0 +1,92 @@
+//! Named colors that restyle a game without recompiling it.
+//!
+//! A theme file lists one color per line, as `name = #rrggbb` or `name = #rrggbbaa`. Empty lines
+//! and lines starting with `//` are ignored.
+//!
+//! Mun code refers to colors by name, with the fields of a struct whose fields are colors, which
+//! [`Theme::apply`] sets. A color is a struct with the fields `r`, `g`, `b` and `a`:
+//!
+//! ```mun
+//! struct Color { r: f32, g: f32, b: f32, a: f32 }
+//!
+//! struct Colors { ball: Color, score: Color }
+//! ```
+
+use crate::inspect::{self, Value};
+use mun_runtime::StructRef;
+use std::{collections::HashMap, fs, path::Path};
+
+/// An RGBA color, with components between `0.0` and `1.0`.
+pub type Color = [f32; 4];
+
+/// A set of named colors.
+#[derive(Clone, Debug, Default)]
+pub struct Theme {
+    colors: HashMap<String, Color>,
+}
+
+/// Parses a color written as `#rrggbb` or `#rrggbbaa`.
+fn parse_color(value: &str) -> Option<Color> {
+    let digits = value.strip_prefix('#')?;
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut color = [1.0; 4];
+    for (index, component) in color.iter_mut().enumerate().take(digits.len() / 2) {
+        let byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok()?;
+        *component = f32::from(byte) / 255.0;
+    }
+    Some(color)
+}
+
+impl Theme {
+    /// Parses the contents of a theme file.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut colors = HashMap::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `name = #rrggbb`", index + 1))?;
+            let color = parse_color(value.trim())
+                .ok_or_else(|| format!("line {}: invalid color '{}'", index + 1, value.trim()))?;
+            colors.insert(name.trim().to_string(), color);
+        }
+        Ok(Self { colors })
+    }
+
+    /// Reads and parses the theme file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        Self::parse(&source).map_err(|e| format!("Failed to parse '{}', {}", path.display(), e))
+    }
+
+    /// Returns the color called `name`, or `default` if the theme doesn't define it.
+    pub fn color(&self, name: &str, default: Color) -> Color {
+        self.colors.get(name).copied().unwrap_or(default)
+    }
+
+    /// Sets every color field of `colors` to the color that the field is named after. Fields that
+    /// the theme doesn't define keep their value.
+    pub fn apply(&self, colors: &mut StructRef) -> Result<(), String> {
+        for field in inspect::fields(colors) {
+            let color = match (field.depth, &field.value, self.colors.get(&field.path)) {
+                (0, Value::Struct(_), Some(color)) => color,
+                _ => continue,
+            };
+            for (component, value) in ["r", "g", "b", "a"].iter().zip(color) {
+                let path = format!("{}.{}", field.path, component);
+                inspect::set(colors, &path, Value::F32(*value))
+                    .map_err(|e| format!("Failed to set `{}`: {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! Helpers for a world that wraps around at its edges, like the game area of `spaceship.mun`.
+
+use std::iter;
+
+/// Returns the offsets at which an object at `position` with `radius` is visible along an axis
+/// that wraps at `extent`: zero, and the offset of its image at the opposite edge while it
+/// straddles an edge.
+fn image_offsets(position: f32, radius: f32, extent: f32) -> impl Iterator<Item = f32> + Clone {
+    let wrapped = if position - radius < 0.0 {
+        Some(extent)
+    } else if position + radius > extent {
+        Some(-extent)
+    } else {
+        None
+    };
+    iter::once(0.0).chain(wrapped)
+}
+
+/// Returns every position at which a circle at `center` with `radius` has to be drawn in a world
+/// of `width` by `height`, starting with `center` itself.
+///
+/// A circle that straddles an edge is also visible at the opposite edge, and one that straddles a
+/// corner is visible in all four corners.
+pub fn images(
+    center: [f32; 2],
+    radius: f32,
+    width: f32,
+    height: f32,
+) -> impl Iterator<Item = [f32; 2]> {
+    let columns = image_offsets(center[0], radius, width);
+    image_offsets(center[1], radius, height).flat_map(move |dy| {
+        columns
+            .clone()
+            .map(move |dx| [center[0] + dx, center[1] + dy])
+    })
+}
+
+/// Returns the distance between `first` and `second` along an axis that wraps at `extent`, the
+/// shorter of the distance through the world and the one across its edge.
+fn wrapped_delta(first: f32, second: f32, extent: f32) -> f32 {
+    let delta = (first - second).abs();
+    if delta > extent / 2.0 {
+        extent - delta
+    } else {
+        delta
+    }
+}
+
+/// Returns the shortest distance between `first` and `second` in a world of `width` by `height`
+/// that wraps at its edges, like `wrapped_distance` in `spaceship.mun`.
+pub fn distance(first: [f32; 2], second: [f32; 2], width: f32, height: f32) -> f32 {
+    let dx = wrapped_delta(first[0], second[0], width);
+    let dy = wrapped_delta(first[1], second[1], height);
+    (dx * dx + dy * dy).sqrt()
+}
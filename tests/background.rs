@@ -0,0 +1,149 @@
+mod common;
+
+use mun_examples::{
+    audio,
+    background::{self, BackgroundSimulation},
+    inspect::Value,
+    invoke, logging, profiler, render_list,
+    snapshot::Snapshot,
+};
+use mun_runtime::{Runtime, RuntimeBuilder, StructRef};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
+
+extern "C" fn rand_f32() -> f32 {
+    0.5
+}
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+fn spawn(munlib: &Path) -> Result<Rc<RefCell<Runtime>>, String> {
+    let runtime = RuntimeBuilder::new(munlib)
+        .insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
+        .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+    audio::register(render_list::register(logging::register(runtime)))
+        .spawn()
+        .map_err(|e| format!("Failed to load munlib: {:?}", e))
+}
+
+fn snapshot(fields: &[(&str, Value)]) -> Snapshot {
+    Snapshot {
+        fields: fields
+            .iter()
+            .map(|(path, value)| (path.to_string(), value.clone()))
+            .collect(),
+    }
+}
+
+/// Receives frames until `done` returns true for the frames so far.
+fn receive_until(
+    simulation: &mut BackgroundSimulation,
+    mut done: impl FnMut(&[background::Frame]) -> bool,
+) -> Result<Vec<background::Frame>, String> {
+    let start = Instant::now();
+    let mut frames = Vec::new();
+    while !done(&frames) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the simulation stalled"
+        );
+        frames.extend(simulation.receive()?);
+        thread::sleep(Duration::from_millis(1));
+    }
+    Ok(frames)
+}
+
+#[test]
+fn failure_to_start_is_reported() {
+    let mut simulation = BackgroundSimulation::spawn(
+        || Err("there is no munlib".to_string()),
+        "new_state",
+        "update",
+        Duration::from_millis(1),
+    );
+    let error = receive_until(&mut simulation, |_| false).unwrap_err();
+    assert_eq!(error, "there is no munlib");
+}
+
+/// The background thread runs the same ticks as the game would on this thread, and sends a frame
+/// for each of them.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn background_pong_matches_pong_on_this_thread() {
+    let munlib: PathBuf = common::build("pong");
+    let input = snapshot(&[
+        ("paddle_left.move_up", Value::Bool(true)),
+        ("paddle_right.move_down", Value::Bool(true)),
+    ]);
+
+    let mut simulation = {
+        let munlib = munlib.clone();
+        BackgroundSimulation::spawn(
+            move || spawn(&munlib),
+            "new_state",
+            "update",
+            Duration::from_millis(1),
+        )
+    };
+    // The input may arrive after the first ticks ran, which aren't compared
+    simulation.set_input(input);
+    let frames = receive_until(&mut simulation, |frames| frames.len() >= 300).unwrap();
+    drop(simulation);
+
+    for (index, frame) in frames.iter().enumerate() {
+        assert_eq!(frame.tick, index as u64);
+        assert!(!frame.reloaded);
+    }
+    let music = frames
+        .iter()
+        .filter(|frame| !frame.audio_requests.is_empty())
+        .count();
+    assert_eq!(music, frames.len(), "every tick sets the music");
+
+    // Replay the ticks from a frame after the input arrived
+    let start = &frames[100];
+    let runtime = spawn(&munlib).unwrap();
+    let runtime_ref = runtime.borrow();
+    let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+    let state = state.root(runtime.clone());
+    let mut state = state.by_ref().clone();
+    start.state.restore(&mut state).unwrap();
+    for frame in &frames[101..] {
+        let _: () = invoke!(&runtime_ref, "update", state.clone()).unwrap();
+        assert_eq!(
+            Snapshot::capture(&state),
+            frame.state,
+            "tick {}",
+            frame.tick
+        );
+    }
+}
+
+/// Mun calls made on the simulation thread are profiled for the frames of the render thread.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn background_calls_are_profiled() {
+    let munlib: PathBuf = common::build("pong");
+    let mut simulation = BackgroundSimulation::spawn(
+        move || spawn(&munlib),
+        "new_state",
+        "update",
+        Duration::from_millis(1),
+    );
+    receive_until(&mut simulation, |frames| frames.len() >= 10).unwrap();
+    drop(simulation);
+
+    profiler::end_frame();
+    let update = profiler::last_frame()
+        .into_iter()
+        .find(|(name, _)| *name == "update")
+        .expect("`update` was not profiled");
+    assert!(update.1.calls >= 10);
+}
@@ -0,0 +1,76 @@
+use mun_examples::{broad_phase::Grid, wrap};
+use proptest::prelude::*;
+
+const WIDTH: f32 = 640.0;
+const HEIGHT: f32 = 360.0;
+const CELL_SIZE: f32 = 32.0;
+
+/// Returns the pairs of overlapping circles, as `object_collide` in `spaceship.mun` finds them.
+fn overlapping_pairs(circles: &[([f32; 2], f32)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (first, &(first_center, first_radius)) in circles.iter().enumerate() {
+        for (second, &(second_center, second_radius)) in circles.iter().enumerate().skip(first + 1)
+        {
+            if wrap::distance(first_center, second_center, WIDTH, HEIGHT)
+                - first_radius
+                - second_radius
+                <= 0.0
+            {
+                pairs.push((first, second));
+            }
+        }
+    }
+    pairs
+}
+
+/// Returns the pairs of overlapping circles among the candidates that the grid finds.
+fn grid_pairs(circles: &[([f32; 2], f32)]) -> Vec<(usize, usize)> {
+    let mut grid = Grid::new(WIDTH, HEIGHT, CELL_SIZE);
+    for (index, &(center, radius)) in circles.iter().enumerate() {
+        grid.insert(index, center, radius);
+    }
+
+    let mut pairs = Vec::new();
+    let mut candidates = Vec::new();
+    for (first, &(center, radius)) in circles.iter().enumerate() {
+        grid.query(center, radius, &mut candidates);
+        for &second in candidates.iter().filter(|&&second| second > first) {
+            let (second_center, second_radius) = circles[second];
+            if wrap::distance(center, second_center, WIDTH, HEIGHT) - radius - second_radius <= 0.0
+            {
+                pairs.push((first, second));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[test]
+fn circles_straddling_edges_overlap_across_them() {
+    let circles = [
+        ([2.0, 100.0], 5.0),
+        ([WIDTH - 2.0, 100.0], 5.0),
+        ([300.0, 1.0], 15.0),
+        ([300.0, HEIGHT - 1.0], 2.0),
+        ([1.0, 1.0], 4.0),
+        ([WIDTH - 1.0, HEIGHT - 1.0], 4.0),
+        ([WIDTH / 2.0, HEIGHT / 2.0], 15.0),
+    ];
+    let pairs = overlapping_pairs(&circles);
+    assert_eq!(pairs, vec![(0, 1), (2, 3), (4, 5)]);
+    assert_eq!(grid_pairs(&circles), pairs);
+}
+
+fn circle() -> impl Strategy<Value = ([f32; 2], f32)> {
+    ([0.0..WIDTH, 0.0..HEIGHT], 1.0..40.0f32)
+}
+
+proptest! {
+    #[test]
+    fn grid_finds_the_same_pairs_as_testing_all_of_them(
+        circles in prop::collection::vec(circle(), 0..64)
+    ) {
+        prop_assert_eq!(grid_pairs(&circles), overlapping_pairs(&circles));
+    }
+}
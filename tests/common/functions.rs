@@ -0,0 +1,169 @@
+//! Calling single functions of a game, with structs built from Rust values.
+//!
+//! Only public Mun functions can be called from Rust, so [`Functions::new`] compiles a variant of
+//! the game in which all of them are. For every struct, the variant also has a function
+//! `default_<Struct>` that creates one with all fields zero, `false` or a default struct, from
+//! which [`Functions::new_struct`] builds a struct with the fields that a test sets.
+//!
+//! The variant is made by editing the source line by line, so it only understands the layout that
+//! the games are written in:
+//!
+//! - a function is declared at the start of a line, with `fn` or `pub fn`,
+//! - a struct is declared at the start of a line, as `struct Name {`, `struct(gc) Name {` or
+//!   `struct(value) Name {`, and
+//! - each field of a struct is on a line of its own, as `name: Type,`, and the struct ends with a
+//!   line that is only `}`.
+//!
+//! Anything else that looks like a declaration panics, so a game written in another layout is
+//! noticed, rather than tested with private functions or structs with the wrong fields.
+
+use super::games;
+use mun_examples::inspect::{self, Value};
+use mun_runtime::{invoke_fn, Runtime, StructRef};
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+pub struct Functions {
+    pub runtime: Rc<RefCell<Runtime>>,
+    /// The default constructor of every struct, by the name of the struct.
+    constructors: Vec<(String, String)>,
+}
+
+impl Functions {
+    /// Compiles `resources/<name>.mun` for testing its functions.
+    pub fn new(name: &str) -> Self {
+        let source = super::source(name);
+        let munlib = super::compile(name, &testable(&source));
+        let constructors = structs(&source)
+            .into_iter()
+            .map(|(name, _)| {
+                let constructor = format!("default_{}", name);
+                (name, constructor)
+            })
+            .collect();
+        Self {
+            runtime: games::runtime(name, &munlib),
+            constructors,
+        }
+    }
+
+    /// Creates a struct of type `name`, with the primitive fields at the dot-separated paths in
+    /// `fields`, e.g. `pos.y`, set, and the others zero.
+    pub fn new_struct<'r>(
+        &self,
+        runtime: &'r Runtime,
+        name: &str,
+        fields: &[(&str, Value)],
+    ) -> StructRef<'r> {
+        let constructor = self
+            .constructors
+            .iter()
+            .find(|(struct_name, _)| struct_name == name)
+            .map(|(_, constructor)| constructor.as_str())
+            .unwrap_or_else(|| panic!("There is no struct called `{}`", name));
+        // Not profiled, unlike `invoke!`, which only takes the names of functions in the source
+        let mut object: StructRef = invoke_fn!(runtime, constructor)
+            .unwrap_or_else(|e| panic!("Failed to call `{}`: {:?}", constructor, e));
+        for (path, value) in fields {
+            inspect::set(&mut object, path, value.clone()).unwrap();
+        }
+        object
+    }
+}
+
+/// Returns `source` with all functions public, and a default constructor for every struct.
+fn testable(source: &str) -> String {
+    let mut testable = String::new();
+    for line in source.lines() {
+        if line.starts_with("fn ") {
+            testable.push_str("pub ");
+        } else if line.trim_start().starts_with("fn ") {
+            panic!("Unsupported function declaration `{}`", line);
+        }
+        testable.push_str(line);
+        testable.push('\n');
+    }
+
+    for (name, fields) in structs(source) {
+        let values = fields
+            .iter()
+            .map(|(field, ty)| format!("{}: {}", field, default_value(ty)))
+            .collect::<Vec<_>>();
+        writeln!(
+            testable,
+            "pub fn default_{0}() -> {0} {{ {0} {{ {1} }} }}",
+            name,
+            values.join(", ")
+        )
+        .unwrap();
+    }
+    testable
+}
+
+/// Returns the name and fields, by name and type, of every struct declared in `source`, which
+/// needs to be in the layout that the module documentation describes.
+fn structs(source: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut lines = source
+        .lines()
+        .map(|line| line.split("//").next().unwrap().trim_end());
+
+    let mut structs = Vec::new();
+    while let Some(line) = lines.next() {
+        let declaration = match line
+            .strip_prefix("pub ")
+            .unwrap_or(line)
+            .strip_prefix("struct")
+        {
+            Some(declaration) => declaration,
+            None => continue,
+        };
+        let declaration = ["(gc)", "(value)"]
+            .iter()
+            .find_map(|kind| declaration.strip_prefix(kind))
+            .unwrap_or(declaration);
+        let name = declaration
+            .strip_prefix(' ')
+            .and_then(|declaration| declaration.strip_suffix(" {"))
+            .filter(|name| is_identifier(name))
+            .unwrap_or_else(|| panic!("Unsupported struct declaration `{}`", line));
+
+        let mut fields = Vec::new();
+        loop {
+            let line = lines
+                .next()
+                .unwrap_or_else(|| panic!("The struct `{}` doesn't end", name))
+                .trim();
+            if line == "}" {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let (field, ty) = line
+                .strip_suffix(',')
+                .unwrap_or(line)
+                .split_once(':')
+                .map(|(field, ty)| (field.trim(), ty.trim()))
+                .filter(|(field, ty)| is_identifier(field) && is_identifier(ty))
+                .unwrap_or_else(|| panic!("Unsupported field `{}` of `{}`", line, name));
+            fields.push((field.to_string(), ty.to_string()));
+        }
+        structs.push((name.to_string(), fields));
+    }
+    structs
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn default_value(ty: &str) -> String {
+    match ty {
+        "bool" => "false".to_string(),
+        "f32" | "f64" => "0.0".to_string(),
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            "0".to_string()
+        }
+        _ => format!("default_{}()", ty),
+    }
+}
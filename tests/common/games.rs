@@ -0,0 +1,103 @@
+//! Both games, run headlessly with the externs of their hosts and seeded random numbers.
+
+use mun_examples::{
+    audio, invoke, logging, particles, random, render_list, snapshot::Snapshot, spaceship,
+};
+use mun_runtime::{RootedStruct, Runtime, RuntimeBuilder, StructRef};
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+/// Loads `munlib`, a build of the game called `name`, with the externs that its host provides.
+pub fn runtime(name: &str, munlib: &Path) -> Rc<RefCell<Runtime>> {
+    let builder = RuntimeBuilder::new(munlib);
+    let builder = if name == "spaceship" {
+        spaceship::register(builder)
+    } else {
+        builder.insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32)
+    };
+    audio::register(particles::register(render_list::register(
+        logging::register(random::register(builder)),
+    )))
+    .spawn()
+    .expect("Failed to load munlib")
+}
+
+/// Pong, with input for the fields of its state, e.g. `paddle_left.move_up`.
+pub struct Pong {
+    pub runtime: Rc<RefCell<Runtime>>,
+    pub state: RootedStruct,
+}
+
+impl Pong {
+    /// Loads `munlib`, and starts a game whose random numbers come from `seed`.
+    pub fn new(munlib: &Path, seed: u64) -> Self {
+        random::seed(seed);
+        let runtime = runtime("pong", munlib);
+        let state = {
+            let runtime_ref = runtime.borrow();
+            let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+            state.root(runtime.clone())
+        };
+        Self { runtime, state }
+    }
+
+    pub fn tick(&mut self, input: &Snapshot) {
+        let mut state = self.state.by_ref().clone();
+        input.restore(&mut state).unwrap();
+        let runtime_ref = self.runtime.borrow();
+        let _: () = invoke!(&runtime_ref, "update", state).unwrap();
+        clear_output();
+    }
+
+    /// Reloads the munlib if it changed, and returns whether it did.
+    pub fn update(&mut self) -> bool {
+        self.runtime.borrow_mut().update()
+    }
+
+    /// Returns all fields of the state.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture(&self.state.by_ref())
+    }
+}
+
+/// The spaceship game, with input for the fields of its `PlayerInput`, e.g. `shoot`.
+///
+/// The ticks are those of [`spaceship::Spaceship`], which `examples/spaceship.rs` runs too.
+pub struct Spaceship {
+    pub game: spaceship::Spaceship,
+}
+
+impl Spaceship {
+    /// Loads `munlib`, and starts a game whose random numbers come from `seed`.
+    pub fn new(munlib: &Path, seed: u64) -> Self {
+        random::seed(seed);
+        let game = spaceship::Spaceship::new(runtime("spaceship", munlib)).unwrap();
+        Self { game }
+    }
+
+    pub fn tick(&mut self, input: &Snapshot) {
+        self.game.tick(input).unwrap();
+        clear_output();
+    }
+
+    /// Reloads the munlib if it changed, and returns whether it did.
+    pub fn update(&mut self) -> bool {
+        self.game.update()
+    }
+
+    /// Returns all fields of the game struct, and of every rocket and asteroid, along with the
+    /// score.
+    pub fn snapshot(&self) -> Snapshot {
+        self.game.snapshot()
+    }
+}
+
+/// Drops what the games requested to draw, play or emit, which nothing shows.
+fn clear_output() {
+    render_list::take();
+    audio::take();
+    particles::take_effects();
+}
@@ -0,0 +1,74 @@
+//! Helpers for tests that run the Mun games in `resources/` headlessly.
+//!
+//! [`build`] compiles a game. The Mun compiler is not a Rust dependency, so it is looked up as the
+//! `mun` executable, or the one the `MUN` environment variable points at. Tests that need it are
+//! ignored unless they are asked for, with `cargo test -- --include-ignored`, and fail when it
+//! can't be found. [`games`] runs the games the way their hosts
+//! do, [`recording`] holds input to play them with, and [`functions`] calls single functions.
+
+// Not every test uses every helper
+#![allow(dead_code)]
+
+pub mod functions;
+pub mod games;
+pub mod recording;
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Returns the source of `resources/<name>.mun`.
+pub fn source(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("resources")
+        .join(format!("{}.mun", name));
+    fs::read_to_string(path).unwrap()
+}
+
+/// Compiles `resources/<name>.mun` into a fresh Mun package, and returns the path of the resulting
+/// munlib.
+///
+/// # Panics
+///
+/// Panics if the Mun compiler is not available.
+pub fn build(name: &str) -> PathBuf {
+    compile(name, &source(name))
+}
+
+/// Compiles `source`, a variant of `resources/<name>.mun`, like [`build`].
+pub fn compile(name: &str, source: &str) -> PathBuf {
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    let mun = env::var_os("MUN").unwrap_or_else(|| "mun".into());
+    let package = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "{}-{}",
+        name,
+        BUILDS.fetch_add(1, Ordering::Relaxed)
+    ));
+    if package.exists() {
+        fs::remove_dir_all(&package).unwrap();
+    }
+
+    match Command::new(&mun).arg("new").arg(&package).status() {
+        Ok(status) => assert!(status.success(), "failed to create a Mun package"),
+        Err(e) => panic!(
+            "the Mun compiler ({:?}) is not available, set `MUN` to its path: {}",
+            mun, e
+        ),
+    }
+
+    fs::write(package.join("src").join("mod.mun"), source).unwrap();
+
+    let status = Command::new(&mun)
+        .arg("build")
+        .arg("--manifest-path")
+        .arg(package.join("mun.toml"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile {}.mun", name);
+
+    package.join("target").join("mod.munlib")
+}
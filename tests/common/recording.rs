@@ -0,0 +1,57 @@
+//! Recorded input for a game, as the ticks at which buttons were pressed and released.
+//!
+//! A recording is text with a line per change, e.g. `120 paddle_left.move_up true`, holding the
+//! button from tick 120 until a later line releases it. Empty lines and lines starting with `#`
+//! are ignored.
+
+use mun_examples::{inspect::Value, snapshot::Snapshot};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    /// The changes, ordered by tick.
+    pub changes: Vec<(u64, String, bool)>,
+}
+
+impl Recording {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut changes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("Invalid change on line {}: `{}`", number + 1, line);
+            let mut words = line.split_whitespace();
+            let (tick, path, pressed) = match (words.next(), words.next(), words.next()) {
+                (Some(tick), Some(path), Some(pressed)) => (tick, path, pressed),
+                _ => return Err(error()),
+            };
+            changes.push((
+                tick.parse().map_err(|_| error())?,
+                path.to_string(),
+                pressed.parse().map_err(|_| error())?,
+            ));
+        }
+        changes.sort_by_key(|(tick, _, _)| *tick);
+        Ok(Self { changes })
+    }
+
+    pub fn to_text(&self) -> String {
+        self.changes
+            .iter()
+            .map(|(tick, path, pressed)| format!("{} {} {}\n", tick, path, pressed))
+            .collect()
+    }
+
+    /// Returns the buttons that were pressed or released by `tick`, and whether they are held.
+    pub fn input(&self, tick: u64) -> Snapshot {
+        let mut input = Snapshot::default();
+        for (_, path, pressed) in self.changes.iter().take_while(|(at, _, _)| *at <= tick) {
+            match input.fields.iter_mut().find(|(field, _)| field == path) {
+                Some((_, value)) => *value = Value::Bool(*pressed),
+                None => input.fields.push((path.clone(), Value::Bool(*pressed))),
+            }
+        }
+        input
+    }
+}
@@ -0,0 +1,138 @@
+//! Tests of single functions of the games, called with structs built in Rust.
+
+mod common;
+
+use common::functions::Functions;
+use mun_examples::{inspect::Value, invoke};
+use mun_runtime::StructRef;
+
+fn vec2(object: &StructRef) -> [f32; 2] {
+    [object.get("x").unwrap(), object.get("y").unwrap()]
+}
+
+fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+    assert!(
+        (actual[0] - expected[0]).abs() < 1e-5 && (actual[1] - expected[1]).abs() < 1e-5,
+        "{:?} is not {:?}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn paddles_move_down_until_the_bottom() {
+    let functions = Functions::new("pong");
+    let runtime = functions.runtime.borrow();
+    let paddle = functions.new_struct(&runtime, "Paddle", &[("pos.y", Value::F32(100.0))]);
+
+    let _: () = invoke!(&runtime, "move_paddle_down", paddle.clone()).unwrap();
+    assert_eq!(vec2(&paddle.get("pos").unwrap()), [0.0, 106.0]);
+
+    // The screen is 600 high, and a paddle 100
+    let paddle = functions.new_struct(&runtime, "Paddle", &[("pos.y", Value::F32(497.0))]);
+    let _: () = invoke!(&runtime, "move_paddle_down", paddle.clone()).unwrap();
+    assert_eq!(vec2(&paddle.get("pos").unwrap()), [0.0, 500.0]);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn balls_are_swept_against_paddles() {
+    let functions = Functions::new("pong");
+    let runtime = functions.runtime.borrow();
+    let paddle = functions.new_struct(
+        &runtime,
+        "Paddle",
+        &[("pos.x", Value::F32(100.0)), ("pos.y", Value::F32(100.0))],
+    );
+
+    // The ball, with a radius of 10, reaches the face of the paddle halfway through the tick
+    let ball = functions.new_struct(
+        &runtime,
+        "Ball",
+        &[
+            ("pos.x", Value::F32(60.0)),
+            ("pos.y", Value::F32(150.0)),
+            ("vel.x", Value::F32(60.0)),
+        ],
+    );
+    let contact: StructRef = invoke!(&runtime, "sweep", ball, paddle.clone()).unwrap();
+    assert_eq!(contact.get::<f32>("time").unwrap(), 0.5);
+    assert_eq!(vec2(&contact.get("normal").unwrap()), [-1.0, 0.0]);
+
+    // Passing above the paddle
+    let ball = functions.new_struct(
+        &runtime,
+        "Ball",
+        &[
+            ("pos.x", Value::F32(60.0)),
+            ("pos.y", Value::F32(50.0)),
+            ("vel.x", Value::F32(60.0)),
+        ],
+    );
+    let contact: StructRef = invoke!(&runtime, "sweep", ball, paddle).unwrap();
+    assert!(contact.get::<f32>("time").unwrap() > 1.0);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn vectors_are_rotated_by_radians() {
+    let functions = Functions::new("spaceship");
+    let runtime = functions.runtime.borrow();
+    let vector = functions.new_struct(
+        &runtime,
+        "Vec2",
+        &[("x", Value::F32(2.0)), ("y", Value::F32(1.0))],
+    );
+
+    let rotated: StructRef = invoke!(
+        &runtime,
+        "rotate_vec2",
+        vector.clone(),
+        std::f32::consts::FRAC_PI_2
+    )
+    .unwrap();
+    assert_near(vec2(&rotated), [-1.0, 2.0]);
+
+    let rotated: StructRef =
+        invoke!(&runtime, "rotate_vec2", vector, std::f32::consts::PI).unwrap();
+    assert_near(vec2(&rotated), [-2.0, -1.0]);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn smaller_asteroids_are_faster() {
+    let functions = Functions::new("spaceship");
+    let runtime = functions.runtime.borrow();
+    let speeds =
+        [3_u8, 2, 1].map(|size| -> f32 { invoke!(&runtime, "asteroid_speed", size).unwrap() });
+    assert_eq!(speeds, [1.0, 2.0, 3.0]);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn objects_collide_across_the_edges() {
+    let functions = Functions::new("spaceship");
+    let runtime = functions.runtime.borrow();
+    let object = |x: f32, y: f32| {
+        functions.new_struct(
+            &runtime,
+            "Object",
+            &[
+                ("position.x", Value::F32(x)),
+                ("position.y", Value::F32(y)),
+                ("radius", Value::F32(5.0)),
+            ],
+        )
+    };
+    let collide = |first: StructRef, second: StructRef| -> bool {
+        invoke!(&runtime, "object_collide", first, second).unwrap()
+    };
+
+    assert!(collide(object(100.0, 100.0), object(108.0, 100.0)));
+    assert!(!collide(object(100.0, 100.0), object(111.0, 100.0)));
+    // The game area is 640 by 360, and wraps around
+    assert!(collide(object(2.0, 100.0), object(636.0, 100.0)));
+    assert!(collide(object(100.0, 358.0), object(100.0, 3.0)));
+    assert!(!collide(object(2.0, 100.0), object(320.0, 100.0)));
+}
@@ -0,0 +1,118 @@
+//! Plays both games with recorded input and a fixed seed, and compares their states with the
+//! golden files in `tests/golden/`, to catch changes to how the games play.
+//!
+//! After a deliberate change, bless the new states with
+//! `BLESS=1 cargo test --test golden -- --include-ignored`, which needs the Mun compiler like the
+//! other tests that play the games. A missing golden file fails the test unless it is blessed.
+
+mod common;
+
+use common::{
+    games::{Pong, Spaceship},
+    recording::Recording,
+};
+use mun_examples::snapshot::Snapshot;
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+const SEED: u64 = 20;
+const TICKS: u64 = 1200;
+/// How many ticks pass between the states in a golden file.
+const INTERVAL: u64 = 100;
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+fn recording(game: &str) -> Recording {
+    let path = golden_dir().join(format!("{}.input", game));
+    Recording::parse(&fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+/// Plays `TICKS` ticks with `tick`, and returns the states every `INTERVAL` ticks as text.
+fn play(mut tick: impl FnMut(&Snapshot) -> Snapshot, recording: &Recording) -> String {
+    let mut text = String::new();
+    for index in 0..TICKS {
+        let state = tick(&recording.input(index));
+        if (index + 1) % INTERVAL == 0 {
+            writeln!(text, "# tick {}", index + 1).unwrap();
+            for (path, value) in state.fields.iter() {
+                writeln!(text, "{} = {:?}", path, value).unwrap();
+            }
+        }
+    }
+    text
+}
+
+/// Compares `actual` with the golden file of `game`, or blesses it.
+fn check(game: &str, actual: &str) {
+    let path = golden_dir().join(format!("{}.golden", game));
+    let bless = env::var_os("BLESS").map_or(false, |bless| bless != "0");
+    if bless {
+        fs::write(&path, actual).unwrap();
+        eprintln!("Wrote the golden file {}", path.display());
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read {}, run with BLESS=1 to write it: {}",
+            path.display(),
+            e
+        )
+    });
+
+    if let Some((line, (expected, actual))) = expected
+        .lines()
+        .zip(actual.lines())
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual)
+    {
+        panic!(
+            "{} differs from {} on line {}:\nexpected: {}\n  actual: {}\n\
+             Run with BLESS=1 if the change is intended",
+            game,
+            path.display(),
+            line + 1,
+            expected,
+            actual
+        );
+    }
+    assert_eq!(
+        expected.lines().count(),
+        actual.lines().count(),
+        "{} differs from {} in length, run with BLESS=1 if the change is intended",
+        game,
+        path.display()
+    );
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_plays_as_recorded() {
+    let munlib = common::build("pong");
+    let mut pong = Pong::new(&munlib, SEED);
+    let actual = play(
+        |input| {
+            pong.tick(input);
+            pong.snapshot()
+        },
+        &recording("pong"),
+    );
+    check("pong", &actual);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn spaceship_plays_as_recorded() {
+    let munlib = common::build("spaceship");
+    let mut spaceship = Spaceship::new(&munlib, SEED);
+    let actual = play(
+        |input| {
+            spaceship.tick(input);
+            spaceship.snapshot()
+        },
+        &recording("spaceship"),
+    );
+    check("spaceship", &actual);
+}
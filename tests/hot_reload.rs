@@ -0,0 +1,327 @@
+//! Keeps both games running while their munlib is replaced over and over by variants with changed
+//! constants, added struct fields and changed function bodies, the way the Mun compiler replaces
+//! it while a game is played.
+//!
+//! Every reload keeps all fields that the state had before and their values, except for the fields
+//! that the previous variant added, and zeroes the fields that were added. The state stays the
+//! same when the garbage collector runs afterwards, because the host keeps it rooted.
+
+mod common;
+
+use common::games::{Pong, Spaceship};
+use mun_examples::{inspect::Value, snapshot::Snapshot};
+use mun_runtime::Runtime;
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// How often every variant is loaded.
+const ROUNDS: usize = 3;
+/// How many ticks pass between two reloads, at least.
+const TICKS_BETWEEN_RELOADS: u64 = 30;
+
+/// A changed source of a game.
+struct Variant {
+    name: &'static str,
+    source: String,
+    /// The fields that the variant adds, where `*` stands for any index, e.g. `rockets.*.damage`.
+    /// Loading another variant afterwards removes them again.
+    added: &'static [&'static str],
+}
+
+/// Returns `source` with every `(from, to)` replacement made, each of which needs to match.
+fn variant(source: &str, replacements: &[(&str, &str)]) -> String {
+    replacements
+        .iter()
+        .fold(source.to_string(), |source, (from, to)| {
+            assert!(source.contains(from), "there is no `{}` to replace", from);
+            source.replace(from, to)
+        })
+}
+
+/// Replaces the munlib at `path` with `munlib`, which the runtime reloads once the lock file that
+/// the Mun compiler holds while writing is gone.
+fn install(munlib: &Path, path: &Path) {
+    let lock = path.with_file_name(".munlock");
+    fs::write(&lock, "").unwrap();
+    fs::copy(munlib, path).unwrap();
+    fs::remove_file(&lock).unwrap();
+}
+
+/// Checks that the fields in `before` kept their values in `after`, that fields that were added
+/// are zero, and that only the `removed` fields are missing.
+fn check_reload(before: &Snapshot, after: &Snapshot, removed: &[&str]) -> Result<(), String> {
+    for (path, _) in before.fields.iter() {
+        if after.get(path).is_none() && !removed.iter().any(|field| matches(field, path)) {
+            return Err(format!("the field `{}` is missing", path));
+        }
+    }
+    for (path, value) in after.fields.iter() {
+        match before.get(path) {
+            Some(previous) if previous != value => {
+                return Err(format!(
+                    "`{}` changed from {:?} to {:?}",
+                    path, previous, value
+                ))
+            }
+            None if !is_zero(value) => {
+                return Err(format!("the added field `{}` is {:?}", path, value))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether `path` is the field `field`, in which `*` stands for any index.
+fn matches(field: &str, path: &str) -> bool {
+    let mut parts = path.split('.');
+    field.split('.').all(|expected| match parts.next() {
+        Some(part) => expected == part || (expected == "*" && part.parse::<usize>().is_ok()),
+        None => false,
+    }) && parts.next().is_none()
+}
+
+fn is_zero(value: &Value) -> bool {
+    match value {
+        Value::Bool(value) => !value,
+        Value::I8(0)
+        | Value::I16(0)
+        | Value::I32(0)
+        | Value::I64(0)
+        | Value::Isize(0)
+        | Value::U8(0)
+        | Value::U16(0)
+        | Value::U32(0)
+        | Value::U64(0)
+        | Value::Usize(0) => true,
+        Value::F32(value) => *value == 0.0,
+        Value::F64(value) => *value == 0.0,
+        _ => false,
+    }
+}
+
+/// Compiles the game called `name` and its `variants`, and plays it while loading each variant in
+/// turn, and the original after them. `update` reloads the munlib of the game once it changed.
+fn soak<G>(
+    name: &str,
+    variants: &[Variant],
+    new_game: impl Fn(&Path) -> G,
+    runtime: impl Fn(&G) -> &Rc<RefCell<Runtime>>,
+    update: impl Fn(&mut G) -> bool,
+    tick: impl Fn(&mut G, u64),
+    snapshot: impl Fn(&G) -> Snapshot,
+) {
+    let original = common::build(name);
+    let mut munlibs = Vec::new();
+    for variant in variants {
+        munlibs.push((
+            variant.name,
+            common::compile(name, &variant.source),
+            variant.added,
+        ));
+    }
+    munlibs.push(("the original", original.clone(), &[]));
+
+    // The runtime watches the directory of the munlib, which only the test writes to
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("soak-{}", name));
+    if directory.exists() {
+        fs::remove_dir_all(&directory).unwrap();
+    }
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("mod.munlib");
+    fs::copy(&original, &path).unwrap();
+
+    let mut game = new_game(&path);
+    let mut ticks = 0;
+    // The fields that the last variant added, which the next one removes
+    let mut removed: &[&str] = &[];
+    for _ in 0..ROUNDS {
+        for (variant, munlib, added) in munlibs.iter() {
+            for _ in 0..TICKS_BETWEEN_RELOADS {
+                tick(&mut game, ticks);
+                ticks += 1;
+            }
+
+            install(munlib, &path);
+            let start = Instant::now();
+            let before = loop {
+                tick(&mut game, ticks);
+                ticks += 1;
+                let before = snapshot(&game);
+                if update(&mut game) {
+                    break before;
+                }
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "{} was not reloaded",
+                    variant
+                );
+            };
+
+            let after = snapshot(&game);
+            if let Err(e) = check_reload(&before, &after, removed) {
+                panic!("after loading {} on tick {}, {}", variant, ticks, e);
+            }
+            removed = added;
+            runtime(&game).borrow().gc_collect();
+            assert_eq!(
+                snapshot(&game),
+                after,
+                "collecting garbage after loading {} changed the state",
+                variant
+            );
+        }
+    }
+}
+
+/// Returns input that turns `fields` on and off every 20 ticks, each at another time.
+fn input(fields: &[&str], tick: u64) -> Snapshot {
+    Snapshot {
+        fields: fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let pressed = (tick / 20 + index as u64) % 3 == 0;
+                (field.to_string(), Value::Bool(pressed))
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn missing_fields_fail_unless_removed() {
+    let snapshot = |paths: &[&str]| Snapshot {
+        fields: paths
+            .iter()
+            .map(|path| (path.to_string(), Value::U8(1)))
+            .collect(),
+    };
+    let before = snapshot(&["score", "rockets.0.damage"]);
+    let after = snapshot(&["score"]);
+    assert!(check_reload(&before, &after, &[]).is_err());
+    assert!(check_reload(&before, &after, &["rockets.*.damage"]).is_ok());
+    assert!(check_reload(&before, &after, &["rockets.*"]).is_err());
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_survives_reloads() {
+    let source = common::source("pong");
+    let variants = [
+        Variant {
+            name: "faster paddles",
+            source: variant(
+                &source,
+                &[(
+                    "fn paddle_speed() -> f32 { 6.0 }",
+                    "fn paddle_speed() -> f32 { 9.0 }",
+                )],
+            ),
+            added: &[],
+        },
+        Variant {
+            name: "a ball with spin",
+            source: variant(
+                &source,
+                &[
+                    ("    vel: Vec2,\n}", "    vel: Vec2,\n    spin: f32,\n}"),
+                    (
+                        "            y: vel_y,\n        },\n    }",
+                        "            y: vel_y,\n        },\n        spin: 1.0,\n    }",
+                    ),
+                ],
+            ),
+            added: &["ball.spin"],
+        },
+        Variant {
+            name: "paddles that move up slowly",
+            source: variant(
+                &source,
+                &[(
+                    "paddle.pos.y -= paddle_speed();",
+                    "paddle.pos.y -= paddle_speed() / 2.0;",
+                )],
+            ),
+            added: &[],
+        },
+    ];
+
+    let fields = [
+        "paddle_left.move_up",
+        "paddle_left.move_down",
+        "paddle_right.move_up",
+        "paddle_right.move_down",
+    ];
+    soak(
+        "pong",
+        &variants,
+        |munlib| Pong::new(munlib, 0),
+        |pong| &pong.runtime,
+        Pong::update,
+        |pong, tick| pong.tick(&input(&fields, tick)),
+        Pong::snapshot,
+    );
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn spaceship_survives_reloads() {
+    let source = common::source("spaceship");
+    let variants = [
+        Variant {
+            name: "faster turns",
+            source: variant(
+                &source,
+                &[(
+                    "fn rotating_speed() -> f32 { 5.0 }",
+                    "fn rotating_speed() -> f32 { 8.0 }",
+                )],
+            ),
+            added: &[],
+        },
+        Variant {
+            name: "rockets that deal damage",
+            source: variant(
+                &source,
+                &[
+                    (
+                        "    life_timer: u8,\n    need_to_destroy: bool,\n}",
+                        "    life_timer: u8,\n    need_to_destroy: bool,\n    damage: u8,\n}",
+                    ),
+                    (
+                        "        life_timer: 0,\n        need_to_destroy: false\n    }",
+                        concat!(
+                            "        life_timer: 0,\n        need_to_destroy: false,\n",
+                            "        damage: 1\n    }",
+                        ),
+                    ),
+                ],
+            ),
+            added: &["rockets.*.damage"],
+        },
+        Variant {
+            name: "rockets that age twice as fast",
+            source: variant(
+                &source,
+                &[("rocket.life_timer += 1;", "rocket.life_timer += 2;")],
+            ),
+            added: &[],
+        },
+    ];
+
+    let fields = ["up", "left", "right", "shoot"];
+    soak(
+        "spaceship",
+        &variants,
+        |munlib| Spaceship::new(munlib, 0),
+        |spaceship| spaceship.game.runtime(),
+        Spaceship::update,
+        |spaceship, tick| spaceship.tick(&input(&fields, tick)),
+        Spaceship::snapshot,
+    );
+}
@@ -0,0 +1,39 @@
+use mun_examples::inspect::Value;
+
+#[test]
+fn integers_change_by_one_per_step() {
+    assert_eq!(Value::I8(1).adjusted(2, 0.1), Some(Value::I8(3)));
+    assert_eq!(Value::U16(1).adjusted(-1, 0.1), Some(Value::U16(0)));
+    assert_eq!(Value::Isize(-4).adjusted(-3, 0.1), Some(Value::Isize(-7)));
+}
+
+#[test]
+fn large_step_counts_saturate() {
+    // 256 and 65536 are multiples of the number of 8 and 16 bit values
+    assert_eq!(Value::I8(0).adjusted(256, 0.1), Some(Value::I8(i8::MAX)));
+    assert_eq!(Value::I8(0).adjusted(-256, 0.1), Some(Value::I8(i8::MIN)));
+    assert_eq!(
+        Value::I16(1).adjusted(65536, 0.1),
+        Some(Value::I16(i16::MAX))
+    );
+    assert_eq!(
+        Value::I16(-1).adjusted(-65536, 0.1),
+        Some(Value::I16(i16::MIN))
+    );
+    assert_eq!(Value::U8(1).adjusted(1000, 0.1), Some(Value::U8(u8::MAX)));
+    assert_eq!(Value::U8(1).adjusted(-1000, 0.1), Some(Value::U8(0)));
+    assert_eq!(
+        Value::I32(i32::MAX - 1).adjusted(i32::MAX, 0.1),
+        Some(Value::I32(i32::MAX))
+    );
+}
+
+#[test]
+fn booleans_flip_per_step_and_structs_cannot_be_adjusted() {
+    assert_eq!(Value::Bool(false).adjusted(3, 0.1), Some(Value::Bool(true)));
+    assert_eq!(
+        Value::Bool(false).adjusted(-2, 0.1),
+        Some(Value::Bool(false))
+    );
+    assert_eq!(Value::Struct("Vec2".to_string()).adjusted(1, 0.1), None);
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use mun_examples::{
+    audio::{self, AudioRequest},
+    inspect::Value,
+    instances::Instances,
+    invoke, logging, random, render_list,
+    snapshot::Snapshot,
+};
+use mun_runtime::{Runtime, RuntimeBuilder, StructRef};
+use std::path::Path;
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+fn spawn(munlib: &Path, count: usize, seed: u64) -> Instances {
+    let runtime =
+        RuntimeBuilder::new(munlib).insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+    let runtime = audio::register(render_list::register(logging::register(random::register(
+        runtime,
+    ))))
+    .spawn()
+    .expect("Failed to load munlib");
+    Instances::new(runtime, count, seed, "new_state").unwrap()
+}
+
+/// Moves the left paddle up for the first `ticks` ticks of every 40, and down for the rest.
+fn input(tick: u64, ticks: u64) -> Snapshot {
+    let up = tick % 40 < ticks;
+    Snapshot {
+        fields: vec![
+            ("paddle_left.move_up".to_string(), Value::Bool(up)),
+            ("paddle_left.move_down".to_string(), Value::Bool(!up)),
+        ],
+    }
+}
+
+fn update_and_draw(_: usize, runtime: &Runtime, state: StructRef) -> Result<(), String> {
+    let _: () = invoke!(runtime, "update", state.clone()).map_err(|e| format!("{:?}", e))?;
+    let _: () = invoke!(runtime, "draw", state).map_err(|e| format!("{:?}", e))?;
+    Ok(())
+}
+
+/// Every game in a batch plays exactly like a game that runs on its own, with the same seed and
+/// input, even though the games use different seeds and input.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn games_do_not_affect_each_other() {
+    let munlib = common::build("pong");
+    let mut batch = spawn(&munlib, 3, 7);
+    let mut alone: Vec<Instances> = (0..3).map(|index| spawn(&munlib, 1, 7 + index)).collect();
+
+    for tick in 0..600 {
+        for (index, game) in alone.iter_mut().enumerate() {
+            let input = input(tick, 10 + index as u64 * 10);
+            batch.get_mut(index).unwrap().input = input.clone();
+            game.get_mut(0).unwrap().input = input;
+            game.step(update_and_draw).unwrap();
+        }
+        batch.step(update_and_draw).unwrap();
+
+        for (index, game) in alone.iter().enumerate() {
+            let (batched, alone) = (batch.get(index).unwrap(), game.get(0).unwrap());
+            assert_eq!(
+                Snapshot::capture(&batched.state()),
+                Snapshot::capture(&alone.state()),
+                "game {} in tick {}",
+                index,
+                tick
+            );
+            assert_eq!(batched.draw_commands(), alone.draw_commands());
+            assert_eq!(batched.audio_requests(), alone.audio_requests());
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn each_game_gets_its_own_output() {
+    let munlib = common::build("pong");
+    let mut batch = spawn(&munlib, 4, 0);
+    for _ in 0..10 {
+        batch.step(update_and_draw).unwrap();
+        for game in batch.iter() {
+            assert!(!game.draw_commands().is_empty());
+            let music = game
+                .audio_requests()
+                .iter()
+                .filter(|request| matches!(request, AudioRequest::SetMusic(_)))
+                .count();
+            assert_eq!(music, 1);
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn seeds_differ_per_game() {
+    let munlib = common::build("pong");
+    let batch = spawn(&munlib, 2, 0);
+    let ball = |index| {
+        Snapshot::capture(&batch.get(index).unwrap().state())
+            .get("ball.vel.y")
+            .cloned()
+    };
+    assert_ne!(ball(0), ball(1));
+}
@@ -0,0 +1,76 @@
+use mun_examples::interpolation::{lerp_angle, Interpolator, Pose};
+
+fn pose(x: f32, y: f32, angle: f32) -> Pose {
+    Pose {
+        position: [x, y],
+        angle,
+    }
+}
+
+/// Records `previous` and `current` as the poses of one object in two consecutive ticks.
+fn interpolator(
+    mut interpolator: Interpolator<u32>,
+    previous: Pose,
+    current: Pose,
+) -> Interpolator<u32> {
+    interpolator.record(0, previous);
+    interpolator.begin_tick();
+    interpolator.record(0, current);
+    interpolator
+}
+
+#[test]
+fn poses_are_interpolated_between_ticks() {
+    let interpolator = interpolator(
+        Interpolator::new(50.0),
+        pose(10.0, 20.0, 0.0),
+        pose(20.0, 10.0, 90.0),
+    );
+    assert_eq!(interpolator.pose(&0, 0.0), Some(pose(10.0, 20.0, 0.0)));
+    assert_eq!(interpolator.pose(&0, 0.5), Some(pose(15.0, 15.0, 45.0)));
+    assert_eq!(interpolator.pose(&0, 1.0), Some(pose(20.0, 10.0, 90.0)));
+}
+
+#[test]
+fn objects_move_across_wrapping_edges() {
+    let interpolator = interpolator(
+        Interpolator::wrapping(800.0, 600.0, 50.0),
+        pose(796.0, 2.0, 0.0),
+        pose(4.0, 598.0, 0.0),
+    );
+    assert_eq!(interpolator.pose(&0, 0.25), Some(pose(798.0, 1.0, 0.0)));
+    assert_eq!(interpolator.pose(&0, 0.75), Some(pose(2.0, 599.0, 0.0)));
+}
+
+#[test]
+fn jumps_are_not_interpolated() {
+    // Without wrapping, crossing the world is a jump
+    let interpolator = interpolator(
+        Interpolator::new(50.0),
+        pose(796.0, 300.0, 0.0),
+        pose(4.0, 300.0, 0.0),
+    );
+    assert_eq!(interpolator.pose(&0, 0.5), Some(pose(4.0, 300.0, 0.0)));
+}
+
+#[test]
+fn new_and_removed_objects() {
+    let mut interpolator = Interpolator::new(50.0);
+    interpolator.record(0, pose(1.0, 1.0, 0.0));
+    interpolator.begin_tick();
+    interpolator.record(1, pose(2.0, 2.0, 0.0));
+
+    assert_eq!(interpolator.pose(&0, 0.5), None);
+    assert_eq!(interpolator.pose(&1, 0.5), Some(pose(2.0, 2.0, 0.0)));
+
+    interpolator.clear();
+    assert_eq!(interpolator.pose(&1, 0.5), None);
+}
+
+#[test]
+fn angles_turn_the_short_way() {
+    assert_eq!(lerp_angle(350.0, 10.0, 0.5), 360.0);
+    assert_eq!(lerp_angle(10.0, 350.0, 0.5), 0.0);
+    assert_eq!(lerp_angle(-170.0, 170.0, 0.25), -175.0);
+    assert_eq!(lerp_angle(0.0, 90.0, 0.5), 45.0);
+}
@@ -0,0 +1,193 @@
+//! Plays both games with random input, and checks what must hold after every tick. A failing case
+//! is shrunk to a minimal recording, which is printed in the format of `tests/golden/*.input`.
+
+mod common;
+
+use common::{
+    functions::Functions,
+    games::{Pong, Spaceship},
+    recording::Recording,
+};
+use mun_examples::{inspect::Value, invoke, snapshot::Snapshot};
+use mun_runtime::StructRef;
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestError, TestRunner},
+};
+use std::{panic, path::PathBuf};
+
+const CASES: u32 = 32;
+const TICKS: u64 = 600;
+/// How many times an input changes at most during a case.
+const MAX_CHANGES: usize = 40;
+
+/// Returns recordings that press and release `fields` at random ticks.
+fn recordings(fields: &'static [&'static str]) -> impl Strategy<Value = Recording> {
+    prop::collection::vec((0..TICKS, 0..fields.len(), any::<bool>()), 0..MAX_CHANGES).prop_map(
+        move |changes| {
+            let mut changes = changes
+                .into_iter()
+                .map(|(tick, field, pressed)| (tick, fields[field].to_string(), pressed))
+                .collect::<Vec<_>>();
+            changes.sort_by_key(|(tick, _, _)| *tick);
+            Recording { changes }
+        },
+    )
+}
+
+/// Plays `CASES` games, each with a random seed and recording, and calls `check` after every tick.
+/// Panics with the smallest input for which `check` fails.
+fn check_invariants<G>(
+    fields: &'static [&'static str],
+    new_game: impl Fn(u64) -> G,
+    tick: impl Fn(&mut G, &Snapshot),
+    check: impl Fn(&G) -> Result<(), String>,
+) {
+    // The minimal input is printed to be kept as a recording, rather than persisted by proptest
+    let mut runner = TestRunner::new(Config {
+        failure_persistence: None,
+        ..Config::with_cases(CASES)
+    });
+    let result = runner.run(&(any::<u64>(), recordings(fields)), |(seed, recording)| {
+        let mut game = new_game(seed);
+        for index in 0..TICKS {
+            tick(&mut game, &recording.input(index));
+            check(&game).map_err(|e| TestCaseError::fail(format!("tick {}: {}", index, e)))?;
+        }
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => {}
+        Err(TestError::Fail(reason, (seed, recording))) => panic!(
+            "{}\nwith seed {} and the input\n{}",
+            reason,
+            seed,
+            recording.to_text()
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn check_range(name: &str, value: f32, min: f32, max: f32) -> Result<(), String> {
+    if value >= min && value <= max {
+        Ok(())
+    } else {
+        Err(format!("{} is {}, outside {}..={}", name, value, min, max))
+    }
+}
+
+/// Failing cases shrink to the shortest recording that still fails.
+#[test]
+fn failures_shrink_to_a_minimal_recording() {
+    // Fails when a button is held for 10 ticks
+    let failure = panic::catch_unwind(|| {
+        check_invariants(
+            &["up", "down"],
+            |_| 0,
+            |held, input| match input.get("up") {
+                Some(Value::Bool(true)) => *held += 1,
+                _ => *held = 0,
+            },
+            |&held| {
+                if held < 10 {
+                    Ok(())
+                } else {
+                    Err("held for too long".to_string())
+                }
+            },
+        )
+    })
+    .unwrap_err();
+
+    let message = failure.downcast_ref::<String>().unwrap();
+    assert!(
+        message.ends_with("with seed 0 and the input\n0 up true\n"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_stays_on_the_screen() {
+    let munlib: PathBuf = common::build("pong");
+    // The limits are private functions, which only the build of `Functions` exports
+    let (screen_height, paddle_height, ball_radius): (f32, f32, f32) = {
+        let functions = Functions::new("pong");
+        let runtime_ref = functions.runtime.borrow();
+        (
+            invoke!(&runtime_ref, "screen_height").unwrap(),
+            invoke!(&runtime_ref, "paddle_height").unwrap(),
+            invoke!(&runtime_ref, "ball_radius").unwrap(),
+        )
+    };
+
+    check_invariants(
+        &[
+            "paddle_left.move_up",
+            "paddle_left.move_down",
+            "paddle_right.move_up",
+            "paddle_right.move_down",
+        ],
+        |seed| Pong::new(&munlib, seed),
+        |pong, input| pong.tick(input),
+        |pong| {
+            let state = pong.state.by_ref();
+            for paddle in ["paddle_left", "paddle_right"] {
+                let pos: StructRef = state.get::<StructRef>(paddle)?.get("pos")?;
+                check_range(paddle, pos.get("y")?, 0.0, screen_height - paddle_height)?;
+            }
+            let pos: StructRef = state.get::<StructRef>("ball")?.get("pos")?;
+            check_range(
+                "ball",
+                pos.get("y")?,
+                ball_radius,
+                screen_height - ball_radius,
+            )
+        },
+    );
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn spaceship_keeps_its_limits() {
+    let munlib: PathBuf = common::build("spaceship");
+    let (max_rockets, rocket_life_time): (usize, u8) = {
+        let functions = Functions::new("spaceship");
+        let runtime_ref = functions.runtime.borrow();
+        (
+            invoke!(&runtime_ref, "max_rockets_amount").unwrap(),
+            invoke!(&runtime_ref, "rocket_life_time").unwrap(),
+        )
+    };
+
+    check_invariants(
+        &["up", "left", "right", "shoot"],
+        |seed| Spaceship::new(&munlib, seed),
+        |spaceship, input| spaceship.tick(input),
+        |spaceship| {
+            let game_struct = spaceship.game.game_struct.by_ref();
+            let object: StructRef = game_struct.get::<StructRef>("spaceship")?.get("object")?;
+            check_range("the angle", object.get("angle")?, -360.0, 360.0)?;
+
+            if spaceship.game.rockets.len() > max_rockets {
+                return Err(format!(
+                    "there are {} rockets, more than {}",
+                    spaceship.game.rockets.len(),
+                    max_rockets
+                ));
+            }
+            for (index, rocket) in spaceship.game.rockets.iter().enumerate() {
+                let life_timer: u8 = rocket.by_ref().get("life_timer")?;
+                if life_timer > rocket_life_time {
+                    return Err(format!(
+                        "rocket {} lived for {} ticks, more than {}",
+                        index, life_timer, rocket_life_time
+                    ));
+                }
+            }
+            Ok(())
+        },
+    );
+}
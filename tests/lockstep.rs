@@ -0,0 +1,310 @@
+mod common;
+
+use common::games::Spaceship;
+use mun_examples::{
+    audio,
+    inspect::Value,
+    invoke,
+    lockstep::{self, Desync, Lockstep},
+    logging,
+    net::Connection,
+    random, render_list,
+    snapshot::Snapshot,
+    spaceship::PLAYER_INPUTS,
+};
+use mun_runtime::{RuntimeBuilder, StructRef};
+use std::{
+    net::TcpListener,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+fn input(up: bool) -> Snapshot {
+    Snapshot {
+        fields: vec![
+            ("move_up".to_string(), Value::Bool(up)),
+            ("move_down".to_string(), Value::Bool(!up)),
+        ],
+    }
+}
+
+/// A stand-in for a game: the position of two paddles, which move with their player's input.
+fn simulate(state: &mut Snapshot, left: &Snapshot, right: &Snapshot) {
+    for (index, input) in [left, right].iter().enumerate() {
+        let step = if input.get("move_up") == Some(&Value::Bool(true)) {
+            -1
+        } else {
+            1
+        };
+        if let (_, Value::I32(position)) = &mut state.fields[index] {
+            *position += step;
+        }
+    }
+}
+
+/// Runs two peers that pass messages to each other directly, until `ticks` ran or a peer found a
+/// desync. The right peer's state is changed before tick `diverge_at`.
+fn run_peers(ticks: u64, diverge_at: Option<u64>) -> [Option<Desync>; 2] {
+    let mut peers = [Lockstep::default(), Lockstep::default()];
+    let state = Snapshot {
+        fields: vec![
+            ("left.y".to_string(), Value::I32(0)),
+            ("right.y".to_string(), Value::I32(0)),
+        ],
+    };
+    let mut states = [state.clone(), state];
+
+    for frame in 0..ticks * 2 {
+        for (index, peer) in peers.iter_mut().enumerate() {
+            // The peers don't always tick in the same frame
+            if (frame + index as u64) % 3 != 0 {
+                let up = (peer.tick() / 7 + index as u64) % 2 == 0;
+                let state = &states[index];
+                peer.set_input(|| input(up), || state.clone());
+            }
+        }
+        let [left, right] = &mut peers;
+        for message in left.outgoing() {
+            right.receive(message);
+        }
+        for message in right.outgoing() {
+            left.receive(message);
+        }
+        if peers.iter().all(|peer| peer.desync().is_some()) {
+            break;
+        }
+
+        for (index, peer) in peers.iter_mut().enumerate() {
+            if let Some((local, remote)) = peer.advance() {
+                if index == 1 && Some(peer.tick() - 1) == diverge_at {
+                    states[1].fields[0].1 = Value::I32(1000);
+                }
+                let (left, right) = if index == 0 {
+                    (&local, &remote)
+                } else {
+                    (&remote, &local)
+                };
+                simulate(&mut states[index], left, right);
+            }
+        }
+    }
+
+    assert!(
+        peers
+            .iter()
+            .all(|peer| peer.tick() >= ticks.min(diverge_at.unwrap_or(ticks))),
+        "the peers stalled"
+    );
+    [peers[0].desync().cloned(), peers[1].desync().cloned()]
+}
+
+#[test]
+fn equal_simulations_stay_in_sync() {
+    assert_eq!(run_peers(200, None), [None, None]);
+}
+
+#[test]
+fn desync_reports_first_divergent_field() {
+    let [left, right] = run_peers(200, Some(50));
+    for desync in [left, right] {
+        let desync = desync.expect("the desync was not found");
+        assert_eq!(desync.tick, 51);
+        let difference = desync.difference.expect("the states were equal");
+        assert_eq!(difference.path, "left.y");
+        assert_ne!(difference.local, difference.remote);
+    }
+}
+
+#[test]
+fn hash_depends_on_every_field() {
+    let state = Snapshot {
+        fields: vec![
+            ("x".to_string(), Value::F32(1.0)),
+            ("alive".to_string(), Value::Bool(true)),
+        ],
+    };
+    let mut moved = state.clone();
+    moved.fields[0].1 = Value::F32(1.0001);
+    let mut dead = state.clone();
+    dead.fields[1].1 = Value::Bool(false);
+
+    assert_eq!(lockstep::hash(&state), lockstep::hash(&state.clone()));
+    assert_ne!(lockstep::hash(&state), lockstep::hash(&moved));
+    assert_ne!(lockstep::hash(&state), lockstep::hash(&dead));
+    assert_eq!(
+        lockstep::first_difference(&state, &moved).map(|difference| difference.path),
+        Some("x".to_string())
+    );
+}
+
+/// Plays pong as one lockstep peer, on its own thread so it has its own random numbers, and
+/// returns the final state or the desync it found. Changes `ball.pos.x` before tick `diverge_at`.
+fn pong_peer(
+    munlib: PathBuf,
+    connection: Connection,
+    paddle: &'static str,
+    ticks: u64,
+    diverge_at: Option<u64>,
+) -> thread::JoinHandle<Result<Snapshot, Desync>> {
+    thread::spawn(move || {
+        let mut connection = connection;
+        random::seed(42);
+        let runtime =
+            RuntimeBuilder::new(munlib).insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+        let runtime = audio::register(render_list::register(logging::register(random::register(
+            runtime,
+        ))))
+        .spawn()
+        .expect("Failed to load munlib");
+        let runtime_ref = runtime.borrow();
+        let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+        let state = state.root(runtime.clone());
+        let other = if paddle == "paddle_left" {
+            "paddle_right"
+        } else {
+            "paddle_left"
+        };
+
+        let mut lockstep = Lockstep::default();
+        let start = Instant::now();
+        while lockstep.tick() < ticks {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "the peers stalled"
+            );
+
+            let state = state.by_ref().clone();
+            let up = (lockstep.tick() / 20) % 2 == 0;
+            lockstep.set_input(|| input(up), || Snapshot::capture(&state));
+            for message in connection.receive().unwrap() {
+                lockstep.receive(message);
+            }
+            for message in lockstep.outgoing() {
+                connection.send(&message).unwrap();
+            }
+            if let Some(desync) = lockstep.desync() {
+                return Err(desync.clone());
+            }
+
+            match lockstep.advance() {
+                Some((local, remote)) => {
+                    if Some(lockstep.tick() - 1) == diverge_at {
+                        let mut ball = state.get::<StructRef>("ball").unwrap();
+                        let mut pos = ball.get::<StructRef>("pos").unwrap();
+                        pos.set("x", pos.get::<f32>("x").unwrap() + 1.0).unwrap();
+                        ball.set("pos", pos).unwrap();
+                    }
+                    local
+                        .restore(&mut state.get::<StructRef>(paddle).unwrap())
+                        .unwrap();
+                    remote
+                        .restore(&mut state.get::<StructRef>(other).unwrap())
+                        .unwrap();
+                    let _: () = invoke!(&runtime_ref, "update", state).unwrap();
+                }
+                None => thread::sleep(Duration::from_micros(100)),
+            }
+        }
+        let state = Snapshot::capture(&state.by_ref());
+        Ok(state)
+    })
+}
+
+fn play_pong(ticks: u64, diverge_at: Option<u64>) -> [Result<Snapshot, Desync>; 2] {
+    let munlib = common::build("pong");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = Connection::connect(listener.local_addr().unwrap()).unwrap();
+    let host = Connection::accept(&listener).unwrap();
+
+    let host = pong_peer(munlib.clone(), host, "paddle_left", ticks, None);
+    let client = pong_peer(munlib, client, "paddle_right", ticks, diverge_at);
+    [host.join().unwrap(), client.join().unwrap()]
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_peers_stay_in_sync() {
+    let [host, client] = play_pong(600, None);
+    assert_eq!(host.unwrap(), client.unwrap());
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_desync_is_reported() {
+    let [host, client] = play_pong(600, Some(100));
+    for result in [host, client] {
+        let desync = result.expect_err("the desync was not found");
+        assert_eq!(desync.tick, 101);
+        assert_eq!(desync.difference.unwrap().path, "ball.pos.x");
+    }
+}
+
+/// Plays the spaceship as `player` of a lockstep game, who presses all of their keys on and off.
+fn spaceship_peer(
+    munlib: PathBuf,
+    connection: Connection,
+    player: usize,
+    ticks: u64,
+) -> thread::JoinHandle<Result<Snapshot, Desync>> {
+    thread::spawn(move || {
+        let mut connection = connection;
+        let mut spaceship = Spaceship::new(&munlib, 42);
+        let mut lockstep = Lockstep::default();
+        let start = Instant::now();
+        while lockstep.tick() < ticks {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "the peers stalled"
+            );
+
+            let pressed = (lockstep.tick() / 25 + player as u64) % 2 == 0;
+            let input = Snapshot {
+                fields: PLAYER_INPUTS[player]
+                    .iter()
+                    .map(|field| (field.to_string(), Value::Bool(pressed)))
+                    .collect(),
+            };
+            lockstep.set_input(|| input, || spaceship.snapshot());
+            for message in connection.receive().unwrap() {
+                lockstep.receive(message);
+            }
+            for message in lockstep.outgoing() {
+                connection.send(&message).unwrap();
+            }
+            if let Some(desync) = lockstep.desync() {
+                return Err(desync.clone());
+            }
+
+            match lockstep.advance() {
+                Some((mut local, remote)) => {
+                    local.fields.extend(remote.fields);
+                    spaceship.tick(&local);
+                }
+                None => thread::sleep(Duration::from_micros(100)),
+            }
+        }
+        Ok(spaceship.snapshot())
+    })
+}
+
+/// The spaceship, with its rockets and asteroids, stays the same on both peers while one of them
+/// steers and the other one shoots.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn spaceship_peers_stay_in_sync() {
+    let munlib = common::build("spaceship");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = Connection::connect(listener.local_addr().unwrap()).unwrap();
+    let host = Connection::accept(&listener).unwrap();
+
+    let host = spaceship_peer(munlib.clone(), host, 0, 600);
+    let client = spaceship_peer(munlib, client, 1, 600);
+    let host = host.join().unwrap().unwrap();
+    let client = client.join().unwrap().unwrap();
+    assert_eq!(host, client);
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use mun_examples::{
+    audio,
+    inspect::Value,
+    invoke, logging,
+    net::{Connection, Message},
+    render_list,
+    snapshot::Snapshot,
+};
+use mun_runtime::{RuntimeBuilder, StructRef};
+use std::{
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+extern "C" fn rand_f32() -> f32 {
+    0.5
+}
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+/// Connects two instances over localhost, and returns the host's connection first.
+fn connect() -> (Connection, Connection) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = Connection::connect(listener.local_addr().unwrap()).unwrap();
+    let host = Connection::accept(&listener).unwrap();
+    (host, client)
+}
+
+/// Waits for the next message on `connection`.
+fn receive(connection: &mut Connection) -> Message {
+    let start = Instant::now();
+    loop {
+        if let Some(message) = connection.receive().unwrap().into_iter().next() {
+            return message;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "no message arrived"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn messages_arrive_unchanged() {
+    let (mut host, mut client) = connect();
+
+    let input = Message::Input(Snapshot {
+        fields: vec![
+            ("move_up".to_string(), Value::Bool(true)),
+            ("move_down".to_string(), Value::Bool(false)),
+        ],
+    });
+    client.send(&input).unwrap();
+    assert_eq!(receive(&mut host), input);
+
+    let state = Message::State(Snapshot {
+        fields: vec![
+            ("ball.pos.x".to_string(), Value::F32(-12.5)),
+            ("paddle_left.score".to_string(), Value::U32(7)),
+            ("token".to_string(), Value::U8(2)),
+            ("time".to_string(), Value::F64(1e100)),
+            ("offset".to_string(), Value::I64(i64::MIN)),
+            ("count".to_string(), Value::Usize(3)),
+        ],
+    });
+    host.send(&state).unwrap();
+    assert_eq!(receive(&mut client), state);
+}
+
+#[test]
+fn closed_connection_is_reported() {
+    let (host, mut client) = connect();
+    drop(host);
+
+    let start = Instant::now();
+    while client.receive().is_ok() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "the closed connection was not noticed"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn invalid_messages_are_rejected() {
+    assert!(Message::decode(&[]).is_err());
+    assert!(Message::decode(&[7, 0, 0, 0, 0]).is_err());
+
+    let mut bytes = Message::State(Snapshot::default()).encode();
+    bytes.push(0);
+    assert!(Message::decode(&bytes).is_err());
+
+    let mut bytes = Message::Input(Snapshot {
+        fields: vec![("move_up".to_string(), Value::Bool(true))],
+    })
+    .encode();
+    bytes.pop();
+    assert!(Message::decode(&bytes).is_err());
+}
+
+/// Plays pong between a host and a client over localhost, and checks that the client always draws
+/// the state that the host simulated, while the client's player moves the right paddle.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn client_follows_host() {
+    let munlib = common::build("pong");
+    let spawn = || {
+        let runtime = RuntimeBuilder::new(&munlib)
+            .insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
+            .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+        audio::register(render_list::register(logging::register(runtime)))
+            .spawn()
+            .expect("Failed to load munlib")
+    };
+    let host_runtime = spawn();
+    let client_runtime = spawn();
+    let (mut host, mut client) = connect();
+
+    let host_ref = host_runtime.borrow();
+    let host_state: StructRef = invoke!(&host_ref, "new_state").unwrap();
+    let host_state = host_state.root(host_runtime.clone());
+    let client_ref = client_runtime.borrow();
+    let client_state: StructRef = invoke!(&client_ref, "new_state").unwrap();
+    let client_state = client_state.root(client_runtime.clone());
+
+    for frame in 0..120 {
+        let move_up = frame < 60;
+        client
+            .send(&Message::Input(Snapshot {
+                fields: vec![
+                    ("move_up".to_string(), Value::Bool(move_up)),
+                    ("move_down".to_string(), Value::Bool(!move_up)),
+                ],
+            }))
+            .unwrap();
+
+        match receive(&mut host) {
+            Message::Input(input) => {
+                let mut paddle = host_state
+                    .by_ref()
+                    .get::<StructRef>("paddle_right")
+                    .unwrap();
+                input.restore(&mut paddle).unwrap();
+            }
+            message => panic!("expected input, got {:?}", message),
+        }
+        let _: () = invoke!(&host_ref, "update", host_state.by_ref().clone()).unwrap();
+        let sent = Snapshot::capture(&host_state.by_ref());
+        host.send(&Message::State(sent.clone())).unwrap();
+
+        match receive(&mut client) {
+            Message::State(state) => state.restore(&mut client_state.by_ref().clone()).unwrap(),
+            message => panic!("expected a state, got {:?}", message),
+        }
+        assert_eq!(
+            Snapshot::capture(&client_state.by_ref()),
+            sent,
+            "frame {}",
+            frame
+        );
+    }
+
+    let paddle_y = |state: &StructRef| {
+        let pos = state
+            .get::<StructRef>("paddle_right")
+            .unwrap()
+            .get::<StructRef>("pos")
+            .unwrap();
+        pos.get::<f32>("y").unwrap()
+    };
+    assert_eq!(
+        paddle_y(&client_state.by_ref()),
+        paddle_y(&host_state.by_ref()),
+    );
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use mun_examples::{
+    assets::Manifest,
+    audio::{self, Audio, NullBackend},
+    invoke, logging, render_list,
+};
+use mun_runtime::{Runtime, RuntimeBuilder, StructRef};
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+extern "C" fn rand_f32() -> f32 {
+    0.5
+}
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+const SCREEN_HEIGHT: f32 = 600.0;
+
+fn spawn(munlib: &Path) -> Rc<RefCell<Runtime>> {
+    let runtime = RuntimeBuilder::new(munlib)
+        .insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
+        .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+    audio::register(render_list::register(logging::register(runtime)))
+        .spawn()
+        .expect("Failed to load munlib")
+}
+
+/// Returns the id of the sound called `name` in pong's asset manifest.
+fn sound_id(name: &str) -> u32 {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/pong/manifest.txt");
+    let manifest = Manifest::load(path).unwrap();
+    manifest
+        .sounds
+        .iter()
+        .find(|sound| sound.name == name)
+        .unwrap_or_else(|| panic!("there is no sound called '{}'", name))
+        .id
+}
+
+fn vec2(object: &StructRef, field: &str) -> [f32; 2] {
+    let vec2 = object.get::<StructRef>(field).unwrap();
+    [vec2.get("x").unwrap(), vec2.get("y").unwrap()]
+}
+
+fn set_y(object: &mut StructRef, field: &str, y: f32) {
+    let mut vec2 = object.get::<StructRef>(field).unwrap();
+    vec2.set("y", y).unwrap();
+    object.set(field, vec2).unwrap();
+}
+
+/// Rallies a ball at close to its maximum speed, which covers a paddle's width several times
+/// over per frame, with paddles that always move in front of it. Every bounce off a paddle is
+/// heard.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn fast_rally_does_not_tunnel() {
+    let munlib = common::build("pong");
+    let runtime = spawn(&munlib);
+    let mut audio = Audio::default();
+    let mut backend = NullBackend::default();
+
+    let runtime_ref = runtime.borrow();
+    let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+    let state = state.root(runtime.clone());
+    let ball_radius: f32 = invoke!(&runtime_ref, "ball_radius").unwrap();
+    let ball_speed_max: f32 = invoke!(&runtime_ref, "ball_speed_max").unwrap();
+    let paddle_width: f32 = invoke!(&runtime_ref, "paddle_width").unwrap();
+    let paddle_height: f32 = invoke!(&runtime_ref, "paddle_height").unwrap();
+
+    let mut ball = state.by_ref().get::<StructRef>("ball").unwrap();
+    let mut vel = ball.get::<StructRef>("vel").unwrap();
+    vel.set("x", -0.9 * ball_speed_max).unwrap();
+    vel.set("y", 0.0).unwrap();
+    ball.set("vel", vel).unwrap();
+    assert!(
+        0.9 * ball_speed_max > paddle_width + 2.0 * ball_radius,
+        "the ball is too slow to tunnel through a paddle"
+    );
+
+    let mut paddles = [
+        state.by_ref().get::<StructRef>("paddle_left").unwrap(),
+        state.by_ref().get::<StructRef>("paddle_right").unwrap(),
+    ];
+    let left_face = vec2(&paddles[0], "pos")[0] + paddle_width + ball_radius;
+    let right_face = vec2(&paddles[1], "pos")[0] - ball_radius;
+
+    let mut bounces = 0;
+    for frame in 0..3000 {
+        let pos = vec2(&ball, "pos");
+        let vel = vec2(&ball, "vel");
+
+        // Move both paddles to where the ball will reach them, slightly off center so it bounces
+        // at varying angles
+        let face = if vel[0] < 0.0 { left_face } else { right_face };
+        let time = ((face - pos[0]) / vel[0]).clamp(0.0, 1.0);
+        let offset = 10.0 * (frame as f32 / 20.0).sin();
+        let paddle_y = (pos[1] + vel[1] * time + offset - paddle_height / 2.0)
+            .clamp(0.0, SCREEN_HEIGHT - paddle_height);
+        for paddle in paddles.iter_mut() {
+            set_y(paddle, "pos", paddle_y);
+        }
+
+        let _: () = invoke!(&runtime_ref, "update", state.by_ref().clone()).unwrap();
+        audio.play_requests(&mut backend);
+
+        for paddle in paddles.iter() {
+            assert_eq!(paddle.get::<u32>("score").unwrap(), 0, "frame {}", frame);
+        }
+        let new_pos = vec2(&ball, "pos");
+        assert!(
+            new_pos[0] >= left_face - 0.01 && new_pos[0] <= right_face + 0.01,
+            "the ball passed a paddle in frame {}: {:?}",
+            frame,
+            new_pos
+        );
+        if vec2(&ball, "vel")[0].signum() != vel[0].signum() {
+            bounces += 1;
+        }
+    }
+
+    assert!(bounces >= 100, "only {} bounces", bounces);
+
+    let hit_sound = sound_id("hit");
+    let hits = backend.sounds.iter().filter(|&&id| id == hit_sound).count();
+    assert_eq!(hits, bounces, "a bounce off a paddle was not heard");
+    assert_eq!(
+        backend.music,
+        [sound_id("music")],
+        "the music was not started once"
+    );
+}
+
+/// Lets the ball pass the left paddle, and checks that the right player scores audibly.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn missed_ball_plays_score_sound() {
+    let munlib = common::build("pong");
+    let runtime = spawn(&munlib);
+    let mut audio = Audio::default();
+    let mut backend = NullBackend::default();
+
+    let runtime_ref = runtime.borrow();
+    let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+    let state = state.root(runtime.clone());
+
+    let mut ball = state.by_ref().get::<StructRef>("ball").unwrap();
+    let mut vel = ball.get::<StructRef>("vel").unwrap();
+    vel.set("x", -10.0).unwrap();
+    vel.set("y", 0.0).unwrap();
+    ball.set("vel", vel).unwrap();
+    let mut paddle = state.by_ref().get::<StructRef>("paddle_left").unwrap();
+    set_y(&mut paddle, "pos", 0.0);
+    assert!(
+        vec2(&ball, "pos")[1] > 200.0,
+        "the ball starts in front of the paddle"
+    );
+
+    let right = state.by_ref().get::<StructRef>("paddle_right").unwrap();
+    for _ in 0..200 {
+        let _: () = invoke!(&runtime_ref, "update", state.by_ref().clone()).unwrap();
+        audio.play_requests(&mut backend);
+        if right.get::<u32>("score").unwrap() > 0 {
+            break;
+        }
+    }
+
+    assert_eq!(
+        right.get::<u32>("score").unwrap(),
+        1,
+        "the right player didn't score"
+    );
+    assert!(
+        backend.sounds.contains(&sound_id("score")),
+        "scoring was not heard: {:?}",
+        backend.sounds
+    );
+}
@@ -0,0 +1,19 @@
+use mun_examples::profiler;
+use std::{thread, time::Duration};
+
+/// Calls made on another thread, e.g. by a background simulation, show up in the frames that the
+/// render thread finishes.
+#[test]
+fn calls_on_other_threads_are_recorded() {
+    profiler::reset();
+    thread::spawn(|| profiler::record("update", 2, Duration::from_millis(3)))
+        .join()
+        .unwrap();
+    profiler::end_frame();
+
+    let last_frame = profiler::last_frame();
+    assert_eq!(last_frame.len(), 1);
+    assert_eq!(last_frame[0].0, "update");
+    assert_eq!(last_frame[0].1.calls, 2);
+    assert!(profiler::report().contains("Mun calls over 1 frames"));
+}
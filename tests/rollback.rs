@@ -0,0 +1,326 @@
+mod common;
+
+use common::games::Spaceship;
+use mun_examples::{
+    audio,
+    inspect::Value,
+    invoke, logging, random, render_list,
+    rollback::{LatencyLink, Rollback, SavedStruct, Simulation},
+    snapshot::Snapshot,
+};
+use mun_runtime::{RootedStruct, Runtime, RuntimeBuilder, StructRef};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+extern "C" fn rand_f32() -> f32 {
+    0.5
+}
+
+extern "C" fn sqrt(value: f32) -> f32 {
+    value.sqrt()
+}
+
+/// Runs two rollback peers for `ticks` ticks, with `latency` ticks between them, and checks that
+/// both end up with the states of a run in which every input was known in time. `record` returns
+/// the simulation's state after each tick, where later entries replace re-simulated ones.
+fn play<S, F>(
+    mut new_simulation: impl FnMut() -> S,
+    input: impl Fn(usize, u64) -> S::Input,
+    record: F,
+    ticks: u64,
+    latency: u64,
+) -> u64
+where
+    S: Simulation,
+    F: Fn(&S) -> BTreeMap<u64, Snapshot>,
+{
+    let mut reference = new_simulation();
+    for tick in 0..ticks {
+        reference.step(&[input(0, tick), input(1, tick)]);
+    }
+
+    let mut simulations = [new_simulation(), new_simulation()];
+    let mut peers = [
+        Rollback::<S>::new(0, input(1, 0), 8),
+        Rollback::<S>::new(1, input(0, 0), 8),
+    ];
+    let mut links = [LatencyLink::new(latency), LatencyLink::new(latency)];
+    // Stalled peers only move on once per round trip, so they need more frames than ticks
+    let frames = (ticks + 10) * (latency * 2 + 1);
+    let mut corrected = false;
+    for now in 0..frames {
+        // The frame after all inputs are known corrects the last ticks that were predicted wrongly
+        if corrected {
+            break;
+        }
+        corrected = peers.iter().all(|peer| peer.confirmed() >= ticks);
+
+        for player in 0..2 {
+            for (tick, remote) in links[1 - player].receive(now) {
+                peers[player].add_remote_input(tick, remote);
+            }
+            let local = input(player, peers[player].tick());
+            if peers[player].tick() >= ticks {
+                let _ = peers[player].advance(&mut simulations[player], local);
+            } else if let Some(tick) =
+                peers[player].advance(&mut simulations[player], local.clone())
+            {
+                links[player].send(now, (tick, local));
+            }
+        }
+    }
+    assert!(corrected, "the peers stalled");
+
+    let expected = record(&reference);
+    for simulation in &simulations {
+        let actual = record(simulation);
+        for tick in 0..ticks {
+            assert_eq!(actual.get(&tick), expected.get(&tick), "tick {}", tick);
+        }
+    }
+    peers[0].resimulated() + peers[1].resimulated()
+}
+
+/// A stand-in for a game: two paddles that move with their player's input, and a value that
+/// depends on the order of all earlier positions.
+#[derive(Default)]
+struct Paddles {
+    tick: u64,
+    positions: [i64; 2],
+    mix: u64,
+    log: BTreeMap<u64, Snapshot>,
+}
+
+impl Simulation for Paddles {
+    type State = (u64, [i64; 2], u64);
+    type Input = i64;
+
+    fn save(&self) -> Self::State {
+        (self.tick, self.positions, self.mix)
+    }
+
+    fn restore(&mut self, state: &Self::State) {
+        let (tick, positions, mix) = *state;
+        self.tick = tick;
+        self.positions = positions;
+        self.mix = mix;
+    }
+
+    fn step(&mut self, inputs: &[i64; 2]) {
+        for (position, input) in self.positions.iter_mut().zip(inputs) {
+            *position += input;
+        }
+        self.mix = self
+            .mix
+            .wrapping_mul(31)
+            .wrapping_add((self.positions[0] * 7 + self.positions[1]) as u64);
+        self.log.insert(
+            self.tick,
+            Snapshot {
+                fields: vec![
+                    ("left".to_string(), Value::I64(self.positions[0])),
+                    ("right".to_string(), Value::I64(self.positions[1])),
+                    ("mix".to_string(), Value::U64(self.mix)),
+                ],
+            },
+        );
+        self.tick += 1;
+    }
+}
+
+fn paddle_input(player: usize, tick: u64) -> i64 {
+    ((tick / 5 + player as u64 * 2) % 3) as i64 - 1
+}
+
+#[test]
+fn mispredictions_are_corrected() {
+    let resimulated = play(
+        Paddles::default,
+        paddle_input,
+        |paddles| paddles.log.clone(),
+        300,
+        5,
+    );
+    assert!(resimulated > 0, "no tick was predicted wrongly");
+}
+
+#[test]
+fn peers_wait_when_predicting_too_far() {
+    // The latency is longer than the 8 ticks the peers may predict
+    play(
+        Paddles::default,
+        paddle_input,
+        |paddles| paddles.log.clone(),
+        300,
+        20,
+    );
+}
+
+#[test]
+fn without_latency_nothing_is_resimulated() {
+    let resimulated = play(
+        Paddles::default,
+        |_, _| 1,
+        |paddles| paddles.log.clone(),
+        100,
+        0,
+    );
+    assert_eq!(resimulated, 0);
+}
+
+#[test]
+fn latency_link_delivers_in_order() {
+    let mut link = LatencyLink::new(3);
+    link.send(0, "first");
+    link.send(1, "second");
+    link.send(1, "third");
+    assert!(link.receive(2).is_empty());
+    assert_eq!(link.receive(3), ["first"]);
+    assert_eq!(link.receive(10), ["second", "third"]);
+    assert!(link.receive(10).is_empty());
+}
+
+/// Pong, with each paddle's input as a snapshot of its `move_up` and `move_down` fields.
+struct Pong {
+    runtime: Rc<RefCell<Runtime>>,
+    state: RootedStruct,
+    tick: u64,
+    log: BTreeMap<u64, Snapshot>,
+}
+
+impl Pong {
+    fn new(munlib: &std::path::Path) -> Self {
+        let runtime = RuntimeBuilder::new(munlib)
+            .insert_fn("rand_f32", rand_f32 as extern "C" fn() -> f32)
+            .insert_fn("sqrt", sqrt as extern "C" fn(value: f32) -> f32);
+        let runtime = audio::register(render_list::register(logging::register(runtime)))
+            .spawn()
+            .expect("Failed to load munlib");
+        let state = {
+            let runtime_ref = runtime.borrow();
+            let state: StructRef = invoke!(&runtime_ref, "new_state").unwrap();
+            state.root(runtime.clone())
+        };
+        Self {
+            runtime,
+            state,
+            tick: 0,
+            log: BTreeMap::new(),
+        }
+    }
+}
+
+impl Simulation for Pong {
+    type State = (u64, SavedStruct);
+    type Input = Snapshot;
+
+    fn save(&self) -> Self::State {
+        (self.tick, SavedStruct::save(&self.state.by_ref()))
+    }
+
+    fn restore(&mut self, state: &Self::State) {
+        self.tick = state.0;
+        state.1.restore(&mut self.state.by_ref().clone()).unwrap();
+    }
+
+    fn step(&mut self, inputs: &[Snapshot; 2]) {
+        let state = self.state.by_ref();
+        for (paddle, input) in ["paddle_left", "paddle_right"].iter().zip(inputs) {
+            input
+                .restore(&mut state.get::<StructRef>(paddle).unwrap())
+                .unwrap();
+        }
+        let runtime = self.runtime.borrow();
+        let _: () = invoke!(&runtime, "update", state.clone()).unwrap();
+        self.log.insert(self.tick, Snapshot::capture(&state));
+        self.tick += 1;
+    }
+}
+
+fn pong_input(player: usize, tick: u64) -> Snapshot {
+    let up = (tick / 15 + player as u64) % 2 == 0;
+    Snapshot {
+        fields: vec![
+            ("move_up".to_string(), Value::Bool(up)),
+            ("move_down".to_string(), Value::Bool(!up)),
+        ],
+    }
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn restored_pong_state_plays_out_the_same() {
+    let munlib = common::build("pong");
+    let mut pong = Pong::new(&munlib);
+    let inputs = |tick| [pong_input(0, tick), pong_input(1, tick)];
+    for tick in 0..50 {
+        pong.step(&inputs(tick));
+    }
+
+    let saved = pong.save();
+    let before = Snapshot::capture(&pong.state.by_ref());
+    for tick in 50..150 {
+        pong.step(&inputs(tick));
+    }
+    let after = Snapshot::capture(&pong.state.by_ref());
+    assert_ne!(after, before);
+
+    pong.restore(&saved);
+    assert_eq!(Snapshot::capture(&pong.state.by_ref()), before);
+    for tick in 50..150 {
+        pong.step(&inputs(tick));
+    }
+    assert_eq!(Snapshot::capture(&pong.state.by_ref()), after);
+}
+
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn pong_peers_agree_despite_latency() {
+    let munlib = common::build("pong");
+    let resimulated = play(
+        || Pong::new(&munlib),
+        pong_input,
+        |pong| pong.log.clone(),
+        400,
+        6,
+    );
+    assert!(resimulated > 0, "no tick was predicted wrongly");
+}
+
+fn spaceship_input(tick: u64) -> Snapshot {
+    Snapshot {
+        fields: vec![
+            ("shoot".to_string(), Value::Bool(true)),
+            ("up".to_string(), Value::Bool(tick % 90 < 30)),
+            ("left".to_string(), Value::Bool(tick % 40 < 10)),
+        ],
+    }
+}
+
+/// The rockets and asteroids that are destroyed after the spaceship is saved are created again
+/// when it is restored.
+#[test]
+#[ignore = "needs the Mun compiler"]
+fn restored_spaceship_plays_out_the_same() {
+    let munlib = common::build("spaceship");
+    let mut spaceship = Spaceship::new(&munlib, 0);
+    for tick in 0..100 {
+        spaceship.tick(&spaceship_input(tick));
+    }
+
+    let saved = spaceship.game.save();
+    let before = spaceship.snapshot();
+    random::seed(1);
+    for tick in 100..400 {
+        spaceship.tick(&spaceship_input(tick));
+    }
+    let after = spaceship.snapshot();
+    assert_ne!(after, before);
+
+    spaceship.game.restore(&saved).unwrap();
+    assert_eq!(spaceship.snapshot(), before);
+    random::seed(1);
+    for tick in 100..400 {
+        spaceship.tick(&spaceship_input(tick));
+    }
+    assert_eq!(spaceship.snapshot(), after);
+}